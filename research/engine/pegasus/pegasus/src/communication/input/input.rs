@@ -15,18 +15,20 @@
 
 use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use ahash::AHashSet;
 use pegasus_common::downcast::*;
 
 use crate::channel_id::ChannelInfo;
-use crate::communication::input::{InputProxy, InputSession};
+use crate::communication::input::{InputMetrics, InputProxy, InputSession};
 use crate::data::MicroBatch;
 use crate::data_plane::{GeneralPull, Pull};
-use crate::errors::IOResult;
+use crate::errors::{IOError, IOResult};
 use crate::event::emitter::EventEmitter;
 use crate::event::{Event, EventKind};
 use crate::progress::EndOfScope;
@@ -36,11 +38,50 @@ use crate::{Data, Tag};
 pub struct InputBlockGuard {
     pub tag: Tag,
     guard: Arc<AtomicUsize>,
+    deadline: Option<Arc<BlockDeadline>>,
+}
+
+impl InputBlockGuard {
+    fn new(tag: Tag, guard: Arc<AtomicUsize>) -> Self {
+        InputBlockGuard { tag, guard, deadline: None }
+    }
+
+    /// If the block has been released as its deadline passed, the data of the scope may be consumed
+    /// before this guard is dropped; It turns true only once the input is polled after the deadline;
+    pub fn is_expired(&self) -> bool {
+        self.deadline
+            .as_ref()
+            .map(|d| d.is_released())
+            .unwrap_or(false)
+    }
 }
 
 impl Drop for InputBlockGuard {
     fn drop(&mut self) {
-        self.guard.fetch_sub(1, Ordering::SeqCst);
+        if let Some(ref deadline) = self.deadline {
+            deadline.release(&self.guard);
+        } else {
+            self.guard.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// The deadline of a block, which is released either by the guard dropped or by the first poll of the
+/// input after the deadline passed, whichever happens first;
+struct BlockDeadline {
+    deadline: Instant,
+    released: AtomicBool,
+}
+
+impl BlockDeadline {
+    fn release(&self, block_cnt: &AtomicUsize) {
+        if !self.released.swap(true, Ordering::SeqCst) {
+            block_cnt.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn is_released(&self) -> bool {
+        self.released.load(Ordering::SeqCst)
     }
 }
 
@@ -55,6 +96,7 @@ pub struct InputHandle<D: Data> {
     // scope skip manager:
     cancel: TidyTagMap<()>,
     parent_cancel: AHashSet<Tag>,
+    metrics: InputMetrics,
 }
 
 impl<D: Data> InputHandle<D> {
@@ -72,6 +114,7 @@ impl<D: Data> InputHandle<D> {
             event_emitter,
             cancel: TidyTagMap::new(scope_level),
             parent_cancel: AHashSet::new(),
+            metrics: InputMetrics::default(),
         }
     }
 
@@ -79,7 +122,7 @@ impl<D: Data> InputHandle<D> {
         if self.pull.has_next()? {
             Ok(true)
         } else {
-            for s in self.stash_index.iter() {
+            for s in self.stash_index.iter_mut() {
                 if !s.1.is_block() && !s.1.is_empty() {
                     return Ok(true);
                 }
@@ -122,6 +165,16 @@ impl<D: Data> InputHandle<D> {
     }
 
     pub(crate) fn next_of(&mut self, tag: &Tag) -> IOResult<Option<MicroBatch<D>>> {
+        self.next_of_within(tag, 3)
+    }
+
+    /// Like `next_of`, but keep pulling until a batch of `tag` is found or nothing is left to pull,
+    /// stashing the batches of other scopes pulled on the way;
+    pub(crate) fn next_of_exhaust(&mut self, tag: &Tag) -> IOResult<Option<MicroBatch<D>>> {
+        self.next_of_within(tag, usize::MAX)
+    }
+
+    fn next_of_within(&mut self, tag: &Tag, max_pulls: usize) -> IOResult<Option<MicroBatch<D>>> {
         let mut stash_index = std::mem::replace(&mut self.stash_index, Default::default());
         if let Some(stash) = stash_index.get_mut(tag) {
             if !stash.is_block() {
@@ -135,13 +188,15 @@ impl<D: Data> InputHandle<D> {
             }
         }
         self.stash_index = stash_index;
-        for _ in 0..3 {
+        for _ in 0..max_pulls {
             if let Some(dataset) = self.pull()? {
                 if &dataset.tag == tag {
                     return Ok(Some(dataset));
                 } else {
                     self.stash_back(dataset);
                 }
+            } else {
+                break;
             }
         }
 
@@ -152,21 +207,34 @@ impl<D: Data> InputHandle<D> {
         if let Some(queue) = self.stash_index.get_mut(tag) {
             if let Some(ref cnt) = queue.block_cnt {
                 cnt.fetch_add(1, Ordering::SeqCst);
-                InputBlockGuard { tag: tag.clone(), guard: cnt.clone() }
+                InputBlockGuard::new(tag.clone(), cnt.clone())
             } else {
                 let cnt = Arc::new(AtomicUsize::new(1));
                 queue.block_cnt = Some(cnt.clone());
-                InputBlockGuard { tag: tag.clone(), guard: cnt }
+                InputBlockGuard::new(tag.clone(), cnt)
             }
         } else {
             let mut queue = StashedQueue::new();
             let cnt = Arc::new(AtomicUsize::new(1));
             queue.block_cnt = Some(cnt.clone());
             self.stash_index.insert(tag.clone(), queue);
-            InputBlockGuard { tag: tag.clone(), guard: cnt }
+            InputBlockGuard::new(tag.clone(), cnt)
         }
     }
 
+    pub(crate) fn block_until(&mut self, tag: &Tag, deadline: Instant) -> InputBlockGuard {
+        let mut guard = self.block(tag);
+        let deadline = Arc::new(BlockDeadline { deadline, released: AtomicBool::new(false) });
+        let queue = self
+            .stash_index
+            .get_mut(tag)
+            .expect("stash queue of blocked tag not found");
+        queue.deadlines.retain(|d| !d.is_released());
+        queue.deadlines.push(deadline.clone());
+        guard.deadline = Some(deadline);
+        guard
+    }
+
     fn stash_back(&mut self, dataset: MicroBatch<D>) {
         if let Some(stash) = self.stash_index.get_mut(&dataset.tag) {
             stash.stash(dataset);
@@ -178,6 +246,17 @@ impl<D: Data> InputHandle<D> {
         }
     }
 
+    pub(crate) fn stash_front(&mut self, dataset: MicroBatch<D>) {
+        if let Some(queue) = self.stash_index.get_mut(&dataset.tag) {
+            queue.push_front(dataset);
+        } else {
+            let mut queue = StashedQueue::new();
+            let tag = dataset.tag.clone();
+            queue.push_back(dataset);
+            self.stash_index.insert(tag, queue);
+        }
+    }
+
     pub(crate) fn stash_block_front(&mut self, dataset: MicroBatch<D>) -> InputBlockGuard {
         let tag = dataset.tag.clone();
         if let Some(queue) = self.stash_index.get_mut(&dataset.tag) {
@@ -185,11 +264,11 @@ impl<D: Data> InputHandle<D> {
             if let Some(ref cnt) = queue.block_cnt {
                 let pre = cnt.fetch_add(1, Ordering::SeqCst);
                 assert_eq!(0, pre);
-                InputBlockGuard { tag, guard: cnt.clone() }
+                InputBlockGuard::new(tag, cnt.clone())
             } else {
                 let cnt = Arc::new(AtomicUsize::new(1));
                 queue.block_cnt = Some(cnt.clone());
-                InputBlockGuard { tag: tag.clone(), guard: cnt }
+                InputBlockGuard::new(tag.clone(), cnt)
             }
         } else {
             let mut queue = StashedQueue::new();
@@ -197,7 +276,7 @@ impl<D: Data> InputHandle<D> {
             let cnt = Arc::new(AtomicUsize::new(1));
             queue.block_cnt = Some(cnt.clone());
             self.stash_index.insert(tag.clone(), queue);
-            InputBlockGuard { tag, guard: cnt }
+            InputBlockGuard::new(tag, cnt)
         }
     }
 
@@ -206,6 +285,55 @@ impl<D: Data> InputHandle<D> {
         self.current_end.push_back(end);
     }
 
+    pub(crate) fn active_tags(&self) -> Vec<Tag> {
+        let mut tags = vec![];
+        for (tag, stash) in self.stash_index.iter() {
+            if !stash.is_empty() {
+                tags.push((&*tag).clone());
+            }
+        }
+        for end in self.current_end.iter() {
+            if !tags.contains(&end.tag) {
+                tags.push(end.tag.clone());
+            }
+        }
+        tags
+    }
+
+    pub(crate) fn reset_scope(&mut self, tag: &Tag) -> IOResult<()> {
+        let level = tag.len() as u32;
+        if level > self.ch_info.scope_level {
+            let mut err = IOError::new(io::ErrorKind::InvalidInput);
+            err.set_origin(format!(
+                "reset scope {:?} of level {} > {}",
+                tag, level, self.ch_info.scope_level
+            ));
+            return Err(err);
+        }
+        let is_current = level == self.ch_info.scope_level;
+        let is_reset = |t: &Tag| if is_current { t == tag } else { tag.is_parent_of(t) };
+        for (t, stash) in self.stash_index.iter_mut() {
+            if stash.is_block() && is_reset(&*t) {
+                let mut err = IOError::new(io::ErrorKind::InvalidInput);
+                err.set_origin(format!("reset scope {:?} while it is blocked", t));
+                return Err(err);
+            }
+        }
+        trace_worker!("channel[{}] reset scope {:?};", self.ch_info.index(), tag);
+        self.stash_index.retain(|t, _| !is_reset(t));
+        self.cancel.retain(|t, _| !is_reset(t));
+        self.current_end
+            .retain(|end| !is_reset(&end.tag));
+        if !is_current {
+            let is_reset_parent = |t: &Tag| t == tag || tag.is_parent_of(t);
+            self.parent_ends
+                .retain(|end| !is_reset_parent(&end.tag));
+            self.parent_cancel
+                .retain(|t| !is_reset_parent(t));
+        }
+        Ok(())
+    }
+
     pub(crate) fn is_exhaust(&self) -> bool {
         self.data_exhaust
             && self
@@ -246,6 +374,7 @@ impl<D: Data> InputHandle<D> {
                             self.data_exhaust = true;
                         }
                         let end = batch.take_end().expect("unreachable");
+                        self.metrics.ends += 1;
                         trace_worker!(
                             "channel[{}] pulled end of scope{:?} peers: {:?}",
                             self.ch_info.index(),
@@ -254,7 +383,10 @@ impl<D: Data> InputHandle<D> {
                         );
                         self.parent_ends.push_back(end);
                     } else {
+                        self.metrics.datasets += 1;
+                        self.metrics.records += batch.len() as u64;
                         if let Some(end) = batch.take_end() {
+                            self.metrics.ends += 1;
                             batch.set_end(end);
                             if batch.tag.is_root() {
                                 debug_worker!("channel[{}] exhaust;", self.ch_info.index());
@@ -317,6 +449,7 @@ impl<D: Data> InputHandle<D> {
         if level == self.ch_info.scope_level {
             // cancel scopes in current scope level;
             if self.cancel.insert(tag.clone(), ()).is_none() {
+                self.metrics.cancels += 1;
                 trace_worker!(
                     "EARLY_STOP: channel[{}] cancel consume data of {:?};",
                     self.ch_info.index(),
@@ -337,6 +470,7 @@ impl<D: Data> InputHandle<D> {
             // if it's a cancel signal from parent scope;
             assert!(level < self.ch_info.scope_level);
             if self.parent_cancel.insert(tag.clone()) {
+                self.metrics.cancels += 1;
                 let mut stash_index = std::mem::replace(&mut self.stash_index, Default::default());
                 for (child, stash) in stash_index.iter_mut() {
                     if tag.is_parent_of(&*child) {
@@ -352,6 +486,21 @@ impl<D: Data> InputHandle<D> {
         }
     }
 
+    pub(crate) fn is_cancelled(&self, tag: &Tag) -> bool {
+        let level = tag.len() as u32;
+        if level == self.ch_info.scope_level {
+            self.is_discard(tag)
+        } else if level < self.ch_info.scope_level {
+            self.parent_cancel.contains(tag)
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> InputMetrics {
+        self.metrics
+    }
+
     #[inline]
     fn is_discard(&self, tag: &Tag) -> bool {
         let level = tag.len() as u32;
@@ -445,6 +594,13 @@ impl<D: Data> InputProxy for RefWrapInput<D> {
         self.inbound.borrow_mut().block(tag)
     }
 
+    #[inline]
+    fn block_until(&self, tag: &Tag, deadline: Instant) -> InputBlockGuard {
+        self.inbound
+            .borrow_mut()
+            .block_until(tag, deadline)
+    }
+
     fn extract_end(&self) -> Option<EndOfScope> {
         self.inbound.borrow_mut().extract_end()
     }
@@ -456,22 +612,49 @@ impl<D: Data> InputProxy for RefWrapInput<D> {
     fn cancel_scope(&self, tag: &Tag) {
         self.inbound.borrow_mut().cancel_scope(tag)
     }
+
+    fn is_cancelled(&self, tag: &Tag) -> bool {
+        self.inbound.borrow().is_cancelled(tag)
+    }
+
+    fn active_tags(&self) -> Vec<Tag> {
+        self.inbound.borrow().active_tags()
+    }
+
+    fn reset_scope(&self, tag: &Tag) -> IOResult<()> {
+        self.inbound.borrow_mut().reset_scope(tag)
+    }
+
+    fn metrics(&self) -> InputMetrics {
+        self.inbound.borrow().metrics()
+    }
 }
 
 struct StashedQueue<D> {
     skip: bool,
     block_cnt: Option<Arc<AtomicUsize>>,
+    deadlines: Vec<Arc<BlockDeadline>>,
     queue: VecDeque<MicroBatch<D>>,
 }
 
 impl<D> StashedQueue<D> {
     fn new() -> Self {
-        StashedQueue { skip: false, block_cnt: None, queue: VecDeque::new() }
+        StashedQueue { skip: false, block_cnt: None, deadlines: vec![], queue: VecDeque::new() }
     }
 
+    /// Release the blocks whose deadlines passed, and prune the released deadlines;
     #[inline]
-    fn is_block(&self) -> bool {
+    fn is_block(&mut self) -> bool {
         if let Some(ref b) = self.block_cnt {
+            if !self.deadlines.is_empty() {
+                let now = Instant::now();
+                for d in self.deadlines.iter() {
+                    if d.deadline <= now {
+                        d.release(b);
+                    }
+                }
+                self.deadlines.retain(|d| !d.is_released());
+            }
             let blocks = b.load(Ordering::SeqCst);
             blocks != 0
         } else {
@@ -529,3 +712,261 @@ impl<D> DerefMut for StashedQueue<D> {
         &mut self.queue
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use pegasus_common::buffer::Buffer;
+
+    use super::*;
+    use crate::channel_id::ChannelId;
+    use crate::data_plane::{pipeline, GeneralPush, Push};
+    use crate::graph::Port;
+    use crate::progress::DynPeers;
+    use crate::worker_id::WorkerId;
+
+    #[test]
+    fn active_tags_of_input() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        for i in 0..2 {
+            let mut buf = Buffer::new();
+            buf.push(i);
+            tx.push(MicroBatch::new(Tag::from(i), 0, buf.into_read_only()))
+                .unwrap();
+        }
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+        assert!(input.active_tags().is_empty());
+
+        // blocked scopes keep the pulled data buffered;
+        let guards = vec![input.block(&Tag::from(0)), input.block(&Tag::from(1))];
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert!(pulled.is_none());
+        let mut tags = input.active_tags();
+        tags.sort_by_key(|t| t.current_uncheck());
+        assert_eq!(tags, vec![Tag::from(0), Tag::from(1)]);
+
+        drop(guards);
+        let mut inbound = input.inbound.borrow_mut();
+        let consumed = Tag::from(0);
+        while let Some(batch) = inbound.next_of(&consumed).unwrap() {
+            assert_eq!(batch.tag, consumed);
+        }
+        drop(inbound);
+        assert_eq!(input.active_tags(), vec![Tag::from(1)]);
+    }
+
+    #[test]
+    fn reset_scope_of_input() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let tag = Tag::from(0);
+        let mut push = |v: u32, is_last: bool| {
+            let mut buf = Buffer::new();
+            buf.push(v);
+            let mut batch = MicroBatch::new(tag.clone(), 0, buf.into_read_only());
+            if is_last {
+                batch.set_end(EndOfScope::new(tag.clone(), DynPeers::single(0), 1, 1));
+            }
+            tx.push(batch).unwrap();
+        };
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+
+        // the first iteration: data and end are buffered;
+        push(1, true);
+        let guard = input.block(&tag);
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert!(pulled.is_none());
+        assert!(input.reset_scope(&tag).is_err());
+        drop(guard);
+        assert_eq!(input.active_tags(), vec![tag.clone()]);
+        input.reset_scope(&tag).unwrap();
+        assert!(input.active_tags().is_empty());
+        // the end pending to be extracted is also cleared;
+        let end = EndOfScope::new(tag.clone(), DynPeers::single(0), 1, 1);
+        input.inbound.borrow_mut().end_on(end);
+        input.reset_scope(&tag).unwrap();
+        assert!(input.extract_end().is_none());
+
+        // the second iteration starts clean;
+        push(2, false);
+        let mut batch = input
+            .inbound
+            .borrow_mut()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(!batch.is_last());
+        assert_eq!(batch.drain().collect::<Vec<_>>(), vec![2]);
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert!(pulled.is_none());
+        assert!(input.extract_end().is_none());
+    }
+
+    #[test]
+    fn reset_parent_scope_of_input() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 2, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let (parent, other) = (Tag::from(0), Tag::from(1));
+        for p in vec![&parent, &other] {
+            let mut buf = Buffer::new();
+            buf.push(0);
+            tx.push(MicroBatch::new(Tag::inherit(p, 0), 0, buf.into_read_only()))
+                .unwrap();
+            let end = EndOfScope::new(p.clone(), DynPeers::single(0), 1, 1);
+            tx.push(MicroBatch::last(0, end)).unwrap();
+        }
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+        let guards = vec![input.block(&Tag::inherit(&parent, 0)), input.block(&Tag::inherit(&other, 0))];
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert!(pulled.is_none());
+        drop(guards);
+
+        // the child scopes and the pending end of the reset parent are cleared, others are kept;
+        input.reset_scope(&parent).unwrap();
+        assert_eq!(input.active_tags(), vec![Tag::inherit(&other, 0)]);
+        let mut inbound = input.inbound.borrow_mut();
+        let batch = inbound
+            .next()
+            .unwrap()
+            .expect("data of other scope");
+        assert_eq!(batch.tag, Tag::inherit(&other, 0));
+        let end = inbound
+            .extract_end()
+            .expect("end of other scope");
+        assert_eq!(end.tag, other);
+        assert!(inbound.extract_end().is_none());
+    }
+
+    #[test]
+    fn reset_canceled_scope_of_input() {
+        let _guard = crate::worker_id::guard(WorkerId::new(0, 1, 0, 0, 0, 1, false));
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (_tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let (event_tx, mut event_rx) = pipeline::<Event>(ChannelId::new(0, 1));
+        let emitter = EventEmitter::new(vec![GeneralPush::IntraThread(event_tx)]);
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), emitter));
+
+        let tag = Tag::from(0);
+        input.cancel_scope(&tag);
+        assert!(event_rx.next().unwrap().is_some());
+        input.reset_scope(&tag).unwrap();
+        assert!(!input.is_cancelled(&tag));
+        // the cancel already sent to upstream is not undone;
+        assert!(event_rx.next().unwrap().is_none());
+    }
+
+    #[test]
+    fn is_cancelled_of_input() {
+        let _guard = crate::worker_id::guard(WorkerId::new(0, 1, 0, 0, 0, 1, false));
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (_tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let (event_tx, mut event_rx) = pipeline::<Event>(ChannelId::new(0, 1));
+        let emitter = EventEmitter::new(vec![GeneralPush::IntraThread(event_tx)]);
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), emitter));
+
+        let (canceled, sibling) = (Tag::from(0), Tag::from(1));
+        assert!(!input.is_cancelled(&canceled));
+        input.cancel_scope(&canceled);
+        assert!(input.is_cancelled(&canceled));
+        assert!(!input.is_cancelled(&sibling));
+        assert!(!input.is_cancelled(&Tag::Root));
+        // the cancel is propagated to upstream;
+        let event = event_rx
+            .next()
+            .unwrap()
+            .expect("cancel event not found");
+        assert!(matches!(event.take_kind(), EventKind::Cancel((0, tag)) if tag == canceled));
+    }
+
+    #[test]
+    fn metrics_of_input() {
+        let _guard = crate::worker_id::guard(WorkerId::new(0, 1, 0, 0, 0, 1, false));
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let (event_tx, _event_rx) = pipeline::<Event>(ChannelId::new(0, 1));
+        let emitter = EventEmitter::new(vec![GeneralPush::IntraThread(event_tx)]);
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), emitter));
+        assert_eq!(input.metrics(), InputMetrics::default());
+
+        let tag = Tag::from(0);
+        for i in 0..3u32 {
+            let mut buf = Buffer::new();
+            for j in 0..=i {
+                buf.push(j);
+            }
+            let mut batch = MicroBatch::new(tag.clone(), 0, buf.into_read_only());
+            if i == 2 {
+                batch.set_end(EndOfScope::new(tag.clone(), DynPeers::single(0), 1, 1));
+            }
+            tx.push(batch).unwrap();
+        }
+        let mut inbound = input.inbound.borrow_mut();
+        while inbound.next().unwrap().is_some() {}
+        drop(inbound);
+        input.cancel_scope(&Tag::from(1));
+        // canceling a scope twice is counted once;
+        input.cancel_scope(&Tag::from(1));
+
+        let metrics = input.metrics();
+        assert_eq!(metrics, InputMetrics { records: 6, datasets: 3, ends: 1, cancels: 1 });
+        // the counters are cumulative;
+        input.reset_scope(&tag).unwrap();
+        assert_eq!(input.metrics(), metrics);
+    }
+
+    #[test]
+    fn block_until_deadline() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let tag = Tag::from(0);
+        let mut push_one = || {
+            let mut buf = Buffer::new();
+            buf.push(0);
+            tx.push(MicroBatch::new(tag.clone(), 0, buf.into_read_only()))
+                .unwrap();
+        };
+        let input = RefWrapInput::wrap(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+
+        push_one();
+        // the deadline far away blocks the scope until the guard is dropped;
+        let guard = input.block_until(&tag, Instant::now() + Duration::from_secs(3600));
+        // the deadline passed already releases the block once pulling;
+        let expired = input.block_until(&tag, Instant::now());
+        // nothing is released until the input is polled;
+        assert!(!expired.is_expired());
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert!(pulled.is_none());
+        assert!(expired.is_expired());
+        assert!(!guard.is_expired());
+        drop(guard);
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert_eq!(pulled.map(|b| b.tag), Some(tag.clone()));
+        // the released deadlines are pruned;
+        let deadlines = input
+            .inbound
+            .borrow()
+            .stash_index
+            .get(&tag)
+            .map(|s| s.deadlines.len());
+        assert_eq!(deadlines, Some(0));
+        // the expired block should not be released twice;
+        drop(expired);
+
+        push_one();
+        let guard = input.block(&tag);
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert!(pulled.is_none());
+        drop(guard);
+        let pulled = input.inbound.borrow_mut().next().unwrap();
+        assert_eq!(pulled.map(|b| b.tag), Some(tag));
+    }
+}
@@ -13,12 +13,15 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use std::io;
+use std::time::Instant;
+
 use pegasus_common::downcast::*;
 
 use crate::channel_id::ChannelInfo;
 use crate::data::MicroBatch;
 use crate::data_plane::GeneralPull;
-use crate::errors::IOResult;
+use crate::errors::{IOError, IOResult};
 use crate::event::emitter::EventEmitter;
 use crate::progress::EndOfScope;
 use crate::{Data, Tag};
@@ -29,11 +32,75 @@ pub trait InputProxy: AsAny + Send {
 
     fn block(&self, tag: &Tag) -> InputBlockGuard;
 
+    /// Block the scope of `tag` like `block`, but the block is released after the `deadline` passes,
+    /// even if the guard is still alive; Check `InputBlockGuard::is_expired` for this case;
+    ///
+    /// Nothing wakes up the input at the deadline: the release happens on the next poll of this input
+    /// after the deadline, e.g. by `has_outstanding` or pulling data, so it takes effect only when the
+    /// operator is scheduled again;
+    ///
+    /// The default implementation ignores the `deadline` and blocks like `block`;
+    fn block_until(&self, tag: &Tag, deadline: Instant) -> InputBlockGuard {
+        let _ = deadline;
+        self.block(tag)
+    }
+
     fn extract_end(&self) -> Option<EndOfScope>;
 
     fn is_exhaust(&self) -> bool;
 
     fn cancel_scope(&self, tag: &Tag);
+
+    /// Check if the scope of `tag` has been canceled by `cancel_scope`, either by itself or by its parent;
+    /// Long-running operators can check this to stop the work of the scope early;
+    ///
+    /// The default implementation never reports a scope as canceled;
+    fn is_cancelled(&self, tag: &Tag) -> bool {
+        let _ = tag;
+        false
+    }
+
+    /// Get the tags which have data buffered or end pending in this input, without consuming anything;
+    /// Data not pulled from the underlying channel yet is not included;
+    ///
+    /// The default implementation reports no tags;
+    fn active_tags(&self) -> Vec<Tag> {
+        vec![]
+    }
+
+    /// Reset the state of the scope `tag`, i.e. buffered data, pending end and cancel, so that the scope
+    /// can be reused from a clean state, e.g. by the next iteration of a loop; If `tag` is a parent scope,
+    /// all its child scopes in this input are reset; It fails if any of the scopes is blocked;
+    ///
+    /// The cancel of the scope already propagated to upstream by `cancel_scope` is not undone,
+    /// the upstream operators may still skip producing data of the scope after reset;
+    ///
+    /// The default implementation fails as reset is not supported;
+    fn reset_scope(&self, tag: &Tag) -> IOResult<()> {
+        let mut err = IOError::new(io::ErrorKind::Other);
+        err.set_origin(format!("reset scope {:?} is not supported", tag));
+        Err(err)
+    }
+
+    /// Get a snapshot of the cumulative counters of this input, e.g. for observability;
+    ///
+    /// The default implementation reports all counters as zero;
+    fn metrics(&self) -> InputMetrics {
+        InputMetrics::default()
+    }
+}
+
+/// The cumulative counters of an input, which are never reset, even by `reset_scope`;
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputMetrics {
+    /// The number of records pulled, including the discarded ones of canceled scopes;
+    pub records: u64,
+    /// The number of datasets pulled, excluding the end signals of parent scopes;
+    pub datasets: u64,
+    /// The number of ends of scopes pulled, including the ends of parent scopes;
+    pub ends: u64,
+    /// The number of scopes canceled by `cancel_scope`;
+    pub cancels: u64,
 }
 
 mod input;
@@ -18,18 +18,112 @@ use std::cell::RefMut;
 use crate::communication::input::input::InputBlockGuard;
 use crate::communication::input::InputHandle;
 use crate::data::MicroBatch;
-use crate::errors::{ErrorKind, JobExecError};
+use crate::errors::{ErrorKind, IOResult, JobExecError};
 use crate::{Data, Tag};
 
 pub struct InputSession<'a, D: Data> {
     scope_level: u32,
     input: RefMut<'a, InputHandle<D>>,
     block_tmp: Vec<InputBlockGuard>,
+    /// the batch pulled by `peek` but not consumed yet;
+    lookahead: Option<MicroBatch<D>>,
 }
 
 impl<'a, D: Data> InputSession<'a, D> {
     pub fn new(input: RefMut<'a, InputHandle<D>>) -> Self {
-        InputSession { scope_level: input.ch_info.scope_level, input, block_tmp: Vec::new() }
+        InputSession {
+            scope_level: input.ch_info.scope_level,
+            input,
+            block_tmp: Vec::new(),
+            lookahead: None,
+        }
+    }
+
+    /// Get a reference of the next record and its tag without consuming it, the record will still
+    /// be returned by the following `next`, `for_each` or `for_each_batch`;
+    ///
+    /// Return `None` if there is no data available now, or the next batch is the end of a scope;
+    pub fn peek(&mut self) -> IOResult<Option<(&Tag, &D)>> {
+        self.fill_lookahead()?;
+        if let Some(batch) = self.lookahead.as_ref() {
+            Ok(batch.get(0).map(|item| (&batch.tag, item)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Consume the next record and its tag, the record returned by a preceding `peek` is returned
+    /// first;
+    pub fn next(&mut self) -> IOResult<Option<(Tag, D)>> {
+        self.fill_lookahead()?;
+        if let Some(batch) = self.lookahead.as_mut() {
+            if let Some(item) = batch.pop() {
+                return Ok(Some((batch.tag.clone(), item)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fold all records of `tag` available now into `init` by `func`, e.g., to count or sum up the
+    /// records of a scope; Return the accumulator and whether the end of the scope has been reached;
+    /// The records of other scopes are left to be consumed later;
+    pub fn fold_tag<B, F>(&mut self, tag: &Tag, init: B, mut func: F) -> IOResult<(B, bool)>
+    where
+        F: FnMut(B, D) -> B,
+    {
+        let mut acc = init;
+        let mut lookahead = match self.lookahead.take() {
+            Some(batch) if &batch.tag == tag => Some(batch),
+            other => {
+                self.lookahead = other;
+                None
+            }
+        };
+        loop {
+            let mut batch = if let Some(batch) = lookahead.take() {
+                batch
+            } else if let Some(batch) = self.input.next_of_exhaust(tag)? {
+                batch
+            } else {
+                return Ok((acc, false));
+            };
+            let is_last = batch.is_last();
+            if !batch.is_discarded() {
+                for item in batch.drain() {
+                    acc = func(acc, item);
+                }
+            }
+            if !self.on_consumed(is_last, &mut batch) {
+                return Ok((acc, is_last));
+            }
+        }
+    }
+
+    fn fill_lookahead(&mut self) -> IOResult<()> {
+        loop {
+            if let Some(batch) = self.lookahead.as_ref() {
+                if !batch.is_empty() || batch.is_last() {
+                    return Ok(());
+                }
+                // all data of the batch had been consumed by `next`;
+                self.lookahead.take();
+            }
+
+            if let Some(batch) = self.input.next()? {
+                self.lookahead = Some(batch);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    #[inline]
+    fn next_batch(&mut self) -> IOResult<Option<MicroBatch<D>>> {
+        if let Some(batch) = self.lookahead.take() {
+            Ok(Some(batch))
+        } else {
+            self.input.next()
+        }
     }
 
     pub fn for_each<F>(&mut self, mut func: F) -> Result<(), JobExecError>
@@ -49,10 +143,10 @@ impl<'a, D: Data> InputSession<'a, D> {
         F: FnMut(&mut MicroBatch<D>) -> Result<(), JobExecError>,
     {
         loop {
-            if self.input.is_exhaust() {
+            if self.lookahead.is_none() && self.input.is_exhaust() {
                 return Ok(());
             } else {
-                if let Some(mut batch) = self.input.next()? {
+                if let Some(mut batch) = self.next_batch()? {
                     let is_last = batch.is_last();
                     if log_enabled!(log::Level::Trace) {
                         if !batch.is_empty() {
@@ -187,3 +281,159 @@ impl<'a, D: Data> InputSession<'a, D> {
         }
     }
 }
+
+impl<'a, D: Data> Drop for InputSession<'a, D> {
+    fn drop(&mut self) {
+        if let Some(batch) = self.lookahead.take() {
+            if !batch.is_empty() || batch.is_last() {
+                // give back the peeked but not consumed data;
+                self.input.stash_front(batch);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use pegasus_common::buffer::Buffer;
+
+    use super::*;
+    use crate::channel_id::{ChannelId, ChannelInfo};
+    use crate::data_plane::{pipeline, Push};
+    use crate::event::emitter::EventEmitter;
+    use crate::graph::Port;
+    use crate::progress::{DynPeers, EndOfScope};
+
+    #[test]
+    fn fold_tag_of_input() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let mut push = |tag: &Tag, data: Vec<u32>, is_last: bool| {
+            let mut buf = Buffer::new();
+            for i in data {
+                buf.push(i);
+            }
+            let mut batch = MicroBatch::new(tag.clone(), 0, buf.into_read_only());
+            if is_last {
+                batch.set_end(EndOfScope::new(tag.clone(), DynPeers::single(0), 1, 1));
+            }
+            tx.push(batch).unwrap();
+        };
+        let input = RefCell::new(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+        let (tag, other) = (Tag::from(0), Tag::from(1));
+
+        push(&tag, vec![1, 2], false);
+        push(&other, vec![100], false);
+        let mut session = InputSession::new(input.borrow_mut());
+        let (sum, exhausted) = session
+            .fold_tag(&tag, 0, |acc, i| acc + i)
+            .unwrap();
+        assert_eq!((sum, exhausted), (3, false));
+
+        push(&tag, vec![3], true);
+        let (sum, exhausted) = session
+            .fold_tag(&tag, sum, |acc, i| acc + i)
+            .unwrap();
+        assert_eq!((sum, exhausted), (6, true));
+        let end = session.input.extract_end();
+        assert_eq!(end.map(|e| e.tag), Some(tag));
+
+        // the records of the other scope are kept;
+        let (sum, exhausted) = session
+            .fold_tag(&other, 0, |acc, i| acc + i)
+            .unwrap();
+        assert_eq!((sum, exhausted), (100, false));
+    }
+
+    #[test]
+    fn fold_tag_of_many_batches() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 1, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let mut push = |tag: &Tag, v: u32, is_last: bool| {
+            let mut buf = Buffer::new();
+            buf.push(v);
+            let mut batch = MicroBatch::new(tag.clone(), 0, buf.into_read_only());
+            if is_last {
+                batch.set_end(EndOfScope::new(tag.clone(), DynPeers::single(0), 1, 1));
+            }
+            tx.push(batch).unwrap();
+        };
+        let input = RefCell::new(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+        let (tag, other) = (Tag::from(0), Tag::from(1));
+
+        // more batches of other scopes than a single `next_of` pulls are queued ahead;
+        for i in 0..5 {
+            push(&other, i, false);
+        }
+        for i in 1..6 {
+            push(&tag, i, i == 5);
+        }
+        let mut session = InputSession::new(input.borrow_mut());
+        let (sum, exhausted) = session
+            .fold_tag(&tag, 0, |acc, i| acc + i)
+            .unwrap();
+        assert_eq!((sum, exhausted), (15, true));
+
+        let (sum, exhausted) = session
+            .fold_tag(&other, 0, |acc, i| acc + i)
+            .unwrap();
+        assert_eq!((sum, exhausted), (10, false));
+    }
+
+    #[test]
+    fn peek_without_consume() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 0, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let mut buf = Buffer::new();
+        for i in 1..4 {
+            buf.push(i);
+        }
+        tx.push(MicroBatch::new(Tag::Root, 0, buf.into_read_only()))
+            .unwrap();
+        let input = RefCell::new(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+        let mut session = InputSession::new(input.borrow_mut());
+        assert_eq!(session.peek().unwrap(), Some((&Tag::Root, &1)));
+        assert_eq!(session.peek().unwrap(), Some((&Tag::Root, &1)));
+        let mut result = vec![];
+        session
+            .for_each(|item| {
+                result.push(item);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(result, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn peek_end_of_root() {
+        let ch_id = ChannelId::new(0, 0);
+        let ch_info = ChannelInfo::new(ch_id, 0, 1, 1, Port::new(0, 0), Port::new(1, 0));
+        let (mut tx, rx) = pipeline::<MicroBatch<u32>>(ch_id);
+        let mut buf = Buffer::new();
+        for i in 1..4 {
+            buf.push(i);
+        }
+        let mut batch = MicroBatch::new(Tag::Root, 0, buf.into_read_only());
+        batch.set_end(EndOfScope::new(Tag::Root, DynPeers::single(0), 1, 1));
+        tx.push(batch).unwrap();
+        let input = RefCell::new(InputHandle::new(ch_info, rx.into(), EventEmitter::new(vec![])));
+        let mut session = InputSession::new(input.borrow_mut());
+        assert_eq!(session.peek().unwrap(), Some((&Tag::Root, &1)));
+        // the peeked batch is still consumed, even though the input is exhausted;
+        let mut result = vec![];
+        session
+            .for_each(|item| {
+                result.push(item);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(result, vec![1, 2, 3]);
+        let end = session.input.extract_end();
+        assert_eq!(end.map(|e| e.tag), Some(Tag::Root));
+    }
+}
@@ -63,6 +63,22 @@ pub trait Schema {
 
     /// Get a certain edge type's id
     fn get_edge_label_id(&self, edge_type: &str) -> Option<LabelId>;
+
+    /// Get the ids of all vertex types, which are by default the ones with a header or schema
+    fn get_vertex_label_ids(&self) -> Vec<LabelId> {
+        (0..=LabelId::MAX)
+            .filter(|id| {
+                self.get_vertex_header(*id).is_some() || self.get_vertex_schema(*id).is_some()
+            })
+            .collect()
+    }
+
+    /// Get the ids of all edge types, which are by default the ones with a header or schema
+    fn get_edge_label_ids(&self) -> Vec<LabelId> {
+        (0..=LabelId::MAX)
+            .filter(|id| self.get_edge_header(*id).is_some() || self.get_edge_schema(*id).is_some())
+            .collect()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -303,6 +319,14 @@ impl Schema for LDBCGraphSchema {
     fn get_edge_label_id(&self, edge_type: &str) -> Option<LabelId> {
         self.edge_type_to_id.get(edge_type).cloned()
     }
+
+    fn get_vertex_label_ids(&self) -> Vec<LabelId> {
+        self.vertex_type_to_id.values().cloned().collect()
+    }
+
+    fn get_edge_label_ids(&self) -> Vec<LabelId> {
+        self.edge_type_to_id.values().cloned().collect()
+    }
 }
 
 impl JsonConf<LDBCGraphSchemaJson> for LDBCGraphSchemaJson {}
@@ -53,6 +53,7 @@ import org.apache.tinkerpop.gremlin.process.traversal.step.map.*;
 import org.apache.tinkerpop.gremlin.process.traversal.util.DefaultTraversal;
 import org.apache.tinkerpop.gremlin.process.traversal.util.TraversalRing;
 import org.apache.tinkerpop.gremlin.structure.Direction;
+import org.apache.tinkerpop.gremlin.structure.PropertyType;
 
 import java.util.*; // todo: avoid import *
 import java.util.stream.Collectors;
@@ -672,11 +673,16 @@ public class LogicPlanGlobalMap {
                         String[] properties = ((PropertiesStep) t).getPropertyKeys();
                         boolean needAll =
                                 (properties == null || properties.length == 0) ? true : false;
+                        Gremlin.PropertyType returnType =
+                                (((PropertiesStep) t).getReturnType() == PropertyType.PROPERTY)
+                                        ? Gremlin.PropertyType.PROPERTY
+                                        : Gremlin.PropertyType.VALUE;
                         return Gremlin.PropertiesStep.newBuilder()
                                 .setPropKeys(
                                         PlanUtils.convertFrom(
                                                 new ToFetchProperties(
                                                         needAll, Arrays.asList(properties))))
+                                .setReturnType(returnType)
                                 .build();
                     }
                 });
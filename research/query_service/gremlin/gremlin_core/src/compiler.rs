@@ -14,11 +14,13 @@
 //! limitations under the License.
 
 use crate::generated as pb;
+use crate::process::traversal::bulk::install_merge_bulk;
+use crate::process::traversal::loops::{LoopOp, LoopStep};
 use crate::process::traversal::step::accum::Accumulator;
 use crate::process::traversal::step::functions::{CompareFunction, EncodeFunction, KeyFunction};
 use crate::process::traversal::step::*;
-use crate::process::traversal::traverser::Traverser;
-use crate::{str_to_dyn_error, Partitioner};
+use crate::process::traversal::traverser::{Requirement, Traverser};
+use crate::{str_to_dyn_error, FromPb, Partitioner};
 use pegasus::api::function::*;
 use pegasus::api::{Collect, CorrelatedSubTask, Dedup, Filter, Fold, FoldByKey, IterCondition, Iteration, KeyBy, Limit, Map, Merge, Sink, SortBy, Source, Count, SortLimitBy};
 use pegasus::result::ResultSink;
@@ -33,7 +35,6 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 type TraverserMap = Box<dyn MapFunction<Traverser, Traverser>>;
-type TraverserFlatMap = Box<dyn FlatMapFunction<Traverser, Traverser, Target = DynIter<Traverser>>>;
 type TraverserFilter = Box<dyn FilterFunction<Traverser>>;
 type TraverserCompare = Box<dyn CompareFunction<Traverser>>;
 type TraverserLeftJoin = Box<dyn BinaryFunction<Traverser, Vec<Traverser>, Option<Traverser>>>;
@@ -71,6 +72,16 @@ impl FnGenerator {
         Ok(step.gen_source(worker_id.index as usize))
     }
 
+    fn gen_requirement(&self, res: &BinaryResource) -> Result<Requirement, BuildJobError> {
+        let step = decode::<pb::gremlin::GremlinStep>(res)?;
+        if let Some(pb::gremlin::gremlin_step::Step::GraphStep(opt)) = step.step {
+            let requirements_pb = unsafe { std::mem::transmute(opt.traverser_requirements) };
+            Ok(Requirement::from_pb(requirements_pb)?)
+        } else {
+            Err("Unsupported source step in pb_request")?
+        }
+    }
+
     fn gen_shuffle(&self) -> Result<TraverserShuffle, BuildJobError> {
         let p = self.partitioner.clone();
         let num_workers = pegasus::get_current_worker().local_peers as usize;
@@ -143,7 +154,15 @@ impl GremlinJobCompiler {
     }
 
     pub fn install(
-        &self, mut stream: Stream<Traverser>, plan: &[OperatorDef],
+        &self, stream: Stream<Traverser>, plan: &[OperatorDef],
+    ) -> Result<Stream<Traverser>, BuildJobError> {
+        self.install_with(stream, plan, Requirement::empty())
+    }
+
+    /// Install the `plan` on `stream`, where the steps act on the traversers as `requirement`
+    /// of the job asks, e.g. the identical traversers are merged if `Requirement::BULK` is set.
+    pub fn install_with(
+        &self, mut stream: Stream<Traverser>, plan: &[OperatorDef], requirement: Requirement,
     ) -> Result<Stream<Traverser>, BuildJobError> {
         for op in &plan[..] {
             if let Some(ref op_kind) = op.op_kind {
@@ -164,10 +183,12 @@ impl GremlinJobCompiler {
                     server_pb::operator_def::OpKind::Map(map) => {
                         let func = self.udf_gen.gen_map(&map.resource)?;
                         stream = stream.map(move |input| func.exec(input))?;
+                        stream = install_merge_bulk(stream, requirement)?;
                     }
                     server_pb::operator_def::OpKind::FlatMap(flat_map) => {
                         let func = self.udf_gen.gen_flat_map(&flat_map.resource)?;
-                        stream = stream.flat_map(move |input| func.exec(input))?;
+                        stream = install_flat_map(stream, func)?;
+                        stream = install_merge_bulk(stream, requirement)?;
                     }
                     server_pb::operator_def::OpKind::Filter(filter) => {
                         let func = self.udf_gen.gen_filter(&filter.resource)?;
@@ -193,7 +214,7 @@ impl GremlinJobCompiler {
                             server_pb::AccumKind::Cnt => {
                                 stream = stream
                                     .count()?
-                                    .map(|cnt| Ok(Traverser::Object(cnt.into())))?
+                                    .map(|cnt| Ok(Traverser::object(cnt)))?
                                     .into_stream()?;
                             }
                             _ => {
@@ -243,11 +264,17 @@ impl GremlinJobCompiler {
                     }
                     server_pb::operator_def::OpKind::Union(union) => {
                         let (mut ori_stream, sub_stream) = stream.copied()?;
-                        stream = self.install(sub_stream, &union.branches[0].plan[..])?;
+                        stream = self.install_with(
+                            sub_stream,
+                            &union.branches[0].plan[..],
+                            requirement,
+                        )?;
                         for subtask in &union.branches[1..] {
                             let copied = ori_stream.copied()?;
                             ori_stream = copied.0;
-                            stream = self.install(copied.1, &subtask.plan[..])?.merge(stream)?;
+                            stream = self
+                                .install_with(copied.1, &subtask.plan[..], requirement)?
+                                .merge(stream)?;
                         }
                     }
                     server_pb::operator_def::OpKind::Iterate(iter) => {
@@ -263,9 +290,25 @@ impl GremlinJobCompiler {
                             IterCondition::max_iters(iter.max_iters)
                         };
                         if let Some(ref iter_body) = iter.body {
-                            stream = stream.iterate_until(until, |start| {
-                                self.install(start, &iter_body.plan[..])
-                            })?;
+                            if requirement
+                                .intersects(Requirement::NESTED_LOOP | Requirement::SINGLE_LOOP)
+                            {
+                                // the loop counters are kept for `loops()` of each iteration
+                                let enter = LoopStep::new(LoopOp::Start, requirement);
+                                let incr = LoopStep::new(LoopOp::Incr, requirement);
+                                let leave = LoopStep::new(LoopOp::End, requirement);
+                                stream = stream
+                                    .map(move |input| enter.exec(input))?
+                                    .iterate_until(until, |start| {
+                                        self.install_with(start, &iter_body.plan[..], requirement)?
+                                            .map(move |input| incr.exec(input))
+                                    })?
+                                    .map(move |input| leave.exec(input))?;
+                            } else {
+                                stream = stream.iterate_until(until, |start| {
+                                    self.install_with(start, &iter_body.plan[..], requirement)
+                                })?;
+                            }
                         } else {
                             Err("iteration body can't be empty;")?
                         }
@@ -279,7 +322,7 @@ impl GremlinJobCompiler {
                             stream = stream
                                 .apply(|sub_start| {
                                     let sub_end = self
-                                        .install(sub_start, &body.plan[..])?
+                                        .install_with(sub_start, &body.plan[..], requirement)?
                                         .collect::<Vec<Traverser>>()?;
                                     Ok(sub_end)
                                 })?
@@ -308,7 +351,7 @@ impl GremlinJobCompiler {
                     match accum_kind {
                         server_pb::AccumKind::Cnt => stream
                             .count()?
-                            .map(|cnt| Ok(Traverser::Object(cnt.into())))?
+                            .map(|cnt| Ok(Traverser::object(cnt)))?
                             .map(move |trav| ec.encode(trav))?
                             .sink_into(output),
                         _ => stream
@@ -355,9 +398,10 @@ impl JobParser<Traverser, pb::protobuf::Result> for GremlinJobCompiler {
         output: ResultSink<pb::protobuf::Result>,
     ) -> Result<(), BuildJobError> {
         if let Some(source) = plan.source.as_ref() {
+            let requirement = self.udf_gen.gen_requirement(source.resource.as_ref())?;
             let source = input.input_from(self.udf_gen.gen_source(source.resource.as_ref())?)?;
             let stream = if let Some(task) = plan.plan.as_ref() {
-                self.install(source, &task.plan)?
+                self.install_with(source, &task.plan, requirement)?
             } else {
                 source
             };
@@ -15,12 +15,12 @@
 
 use crate::graph_proxy::from_fn;
 use crate::structure::{
-    DefaultDetails, Details, Direction, DynDetails, Edge, Label, LabelId, PropKey, QueryParams,
-    Statement, Vertex, ID_BITS,
+    CachedGraph, DefaultDetails, Details, Direction, DynDetails, Edge, Label, LabelId, PropKey,
+    QueryParams, Statement, Vertex, ID_BITS,
 };
 use crate::{filter_limit, limit_n};
 use crate::{register_graph, DynResult, GraphProxy, ID};
-use dyn_type::BorrowObject;
+use dyn_type::{BorrowObject, Object};
 use graph_store::config::{JsonConf, DIR_GRAPH_SCHEMA, FILE_SCHEMA};
 use graph_store::ldbc::LDBCVertexParser;
 use graph_store::prelude::{
@@ -37,16 +37,16 @@ lazy_static! {
     pub static ref DATA_PATH: String = configure_with_default!(String, "DATA_PATH", "".to_string());
     pub static ref PARTITION_ID: usize = configure_with_default!(usize, "PARTITION_ID", 0);
     pub static ref GRAPH: LargeGraphDB<DefaultId, InternalId> = _init_graph();
-    static ref GRAPH_PROXY: Arc<DemoGraph> = initialize();
+    static ref GRAPH_PROXY: Arc<CachedGraph<DemoGraph>> = initialize();
 }
 
 pub struct DemoGraph {
     store: &'static LargeGraphDB<DefaultId, InternalId>,
 }
 
-fn initialize() -> Arc<DemoGraph> {
+fn initialize() -> Arc<CachedGraph<DemoGraph>> {
     lazy_static::initialize(&GRAPH);
-    Arc::new(DemoGraph { store: &GRAPH })
+    Arc::new(CachedGraph::new(DemoGraph { store: &GRAPH }))
 }
 
 fn _init_graph() -> LargeGraphDB<DefaultId, InternalId> {
@@ -377,30 +377,35 @@ impl LazyVertexDetails {
     pub fn new(id: DefaultId, store: &'static LargeGraphDB<DefaultId, InternalId>) -> Self {
         LazyVertexDetails { id, inner: AtomicPtr::default(), store }
     }
+
+    /// Load the vertex from the store on the first access, or `None` if it does not exist.
+    fn get_vertex_ptr(&self) -> Option<*mut LocalVertex<'static, DefaultId>> {
+        let mut ptr = self.inner.load(Ordering::SeqCst);
+        if ptr.is_null() {
+            if let Some(v) = self.store.get_vertex(self.id) {
+                let v = Box::new(v);
+                let new_ptr = Box::into_raw(v);
+                let swapped = self.inner.swap(new_ptr, Ordering::SeqCst);
+                if swapped.is_null() {
+                    ptr = new_ptr;
+                } else {
+                    unsafe {
+                        std::ptr::drop_in_place(new_ptr);
+                    }
+                    ptr = swapped
+                };
+            } else {
+                return None;
+            }
+        }
+        Some(ptr)
+    }
 }
 
 impl Details for LazyVertexDetails {
     fn get_property(&self, key: &PropKey) -> Option<BorrowObject> {
         if let PropKey::Str(key) = key {
-            let mut ptr = self.inner.load(Ordering::SeqCst);
-            if ptr.is_null() {
-                if let Some(v) = self.store.get_vertex(self.id) {
-                    let v = Box::new(v);
-                    let new_ptr = Box::into_raw(v);
-                    let swapped = self.inner.swap(new_ptr, Ordering::SeqCst);
-                    if swapped.is_null() {
-                        ptr = new_ptr;
-                    } else {
-                        unsafe {
-                            std::ptr::drop_in_place(new_ptr);
-                        }
-                        ptr = swapped
-                    };
-                } else {
-                    return None;
-                }
-            }
-
+            let ptr = self.get_vertex_ptr()?;
             unsafe { (*ptr).get_property(key) }
         } else {
             info!("Have not support getting property by prop_id in experiments store yet");
@@ -415,6 +420,12 @@ impl Details for LazyVertexDetails {
     fn get_label(&self) -> &Label {
         unreachable!()
     }
+
+    fn get_all_properties(&self) -> Option<Vec<(PropKey, Object)>> {
+        let ptr = self.get_vertex_ptr()?;
+        let properties = unsafe { (*ptr).clone_all_properties() }.unwrap_or_default();
+        Some(properties.into_iter().map(|(prop, obj)| (prop.into(), obj)).collect())
+    }
 }
 
 impl Drop for LazyVertexDetails {
@@ -516,7 +527,9 @@ fn encode_storage_edge_label(labels: &Vec<Label>) -> Option<Vec<LabelId>> {
 
 #[cfg(test)]
 mod tests {
-    use super::GRAPH;
+    use super::{LazyEdgeDetails, LazyVertexDetails, GRAPH};
+    use crate::structure::{Details, DynDetails, PropKey};
+    use dyn_type::Object;
     use graph_store::ldbc::LDBCVertexParser;
     use graph_store::prelude::{DefaultId, GlobalStoreTrait};
 
@@ -530,4 +543,21 @@ mod tests {
         let out: Vec<DefaultId> = out_iter.map(|v| v.get_id()).collect();
         assert_eq!(out, vec![v4, v2]);
     }
+
+    #[test]
+    fn get_all_properties_test() {
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let details = DynDetails::new(LazyVertexDetails::new(v1, &GRAPH));
+        let properties = details.get_all_properties().unwrap();
+        let expected: Vec<(PropKey, Object)> = vec![
+            ("age".into(), 29.into()),
+            ("id".into(), 1.into()),
+            ("name".into(), "marko".into()),
+        ];
+        assert_eq!(properties, expected);
+
+        // the details that can not enumerate the properties
+        let details = DynDetails::new(LazyEdgeDetails { store: &GRAPH });
+        assert!(details.get_all_properties().is_none());
+    }
 }
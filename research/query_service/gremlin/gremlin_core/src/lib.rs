@@ -32,7 +32,7 @@ extern crate graph_store;
 extern crate dyn_type;
 
 use crate::process::traversal::traverser::{ShadeSync, Traverser};
-pub use crate::structure::{get_graph, register_graph};
+pub use crate::structure::{clear_graph_local, get_graph, register_graph, register_graph_local};
 pub use crate::structure::{Element, GraphProxy, ID};
 
 pub mod process;
@@ -43,6 +43,7 @@ pub mod compiler;
 pub mod graph_proxy;
 
 use crate::process::traversal::path::ResultPath;
+use crate::process::traversal::step::PropertyElement;
 
 use crate::structure::filter::codec::ParseError;
 pub use generated::gremlin::GremlinStep as GremlinStepPb;
@@ -144,9 +145,44 @@ impl Partitioner for Partition {
     }
 }
 
+/// A partition utility that hashes the ids over all workers of all servers,
+/// assuming that the graph is partitioned into `num_servers * workers` partitions,
+/// and each worker owns the partition identical to its worker id
+pub struct HashPartitioner {
+    pub num_servers: usize,
+}
+
+impl HashPartitioner {
+    /// Fold the 128-bit id into 64 bits, and take the high bits of the Fibonacci hashing,
+    /// which are better mixed than the low bits
+    #[inline]
+    fn hash(id: &ID) -> u64 {
+        let folded = ((*id >> 64) as u64) ^ (*id as u64);
+        folded.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32
+    }
+}
+
+impl Partitioner for HashPartitioner {
+    fn get_partition(&self, id: &ID, workers: usize) -> DynResult<u64> {
+        let partitions = (self.num_servers * workers) as u64;
+        if partitions == 0 {
+            Err(str_to_dyn_error("no partition is available in hash partitioner"))
+        } else {
+            Ok(Self::hash(id) % partitions)
+        }
+    }
+
+    fn get_worker_partitions(
+        &self, _job_workers: usize, worker_id: u32,
+    ) -> DynResult<Option<Vec<u64>>> {
+        Ok(Some(vec![worker_id as u64]))
+    }
+}
+
 pub fn register_gremlin_types() -> io::Result<()> {
     dyn_type::register_type::<ShadeSync<(Traverser, Traverser)>>()?;
     dyn_type::register_type::<ShadeSync<Vec<Traverser>>>()?;
     dyn_type::register_type::<ResultPath>()?;
+    dyn_type::register_type::<PropertyElement>()?;
     Ok(())
 }
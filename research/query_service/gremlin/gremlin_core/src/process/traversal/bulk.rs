@@ -0,0 +1,150 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use crate::process::traversal::traverser::{Requirement, Traverser};
+use pegasus::api::Unary;
+use pegasus::stream::Stream;
+use pegasus::BuildJobError;
+use std::collections::HashMap;
+
+/// Install an operator on `stream` that merges the identical traversers of each batch by
+/// `merge_bulk()`, if `Requirement::BULK` is set. Otherwise, the stream is returned as it is.
+pub fn install_merge_bulk(
+    stream: Stream<Traverser>, requirement: Requirement,
+) -> Result<Stream<Traverser>, BuildJobError> {
+    if !requirement.contains(Requirement::BULK) {
+        return Ok(stream);
+    }
+    stream.unary("merge_bulk", |_info| {
+        move |input, output| {
+            input.for_each_batch(|batch| {
+                if !batch.is_empty() {
+                    let mut session = output.new_session(&batch.tag)?;
+                    for t in merge_bulk(batch.drain(), requirement) {
+                        session.give(t)?;
+                    }
+                }
+                Ok(())
+            })
+        }
+    })
+}
+
+/// Merge the identical traversers by summing up their bulks if `Requirement::BULK` is set,
+/// and keep the order in which the traversers first appear. Only the traversers without path are
+/// merged, see `Traverser::is_bulkable()`, and only if they share the same sack and loops.
+/// Otherwise, the traversers are returned as they are.
+pub fn merge_bulk<I>(traversers: I, requirement: Requirement) -> Vec<Traverser>
+where
+    I: IntoIterator<Item = Traverser>,
+{
+    let traversers = traversers.into_iter();
+    if !requirement.contains(Requirement::BULK) {
+        return traversers.collect();
+    }
+    let mut merged: Vec<Traverser> = vec![];
+    // the traversers of identical heads, which may differ in the sacks or loops
+    let mut index: HashMap<Traverser, Vec<usize>> = HashMap::new();
+    for traverser in traversers {
+        if traverser.is_bulkable() {
+            let candidates = index.entry(traverser.clone()).or_default();
+            if let Some(idx) =
+                candidates.iter().find(|idx| is_mergeable(&merged[**idx], &traverser))
+            {
+                merged[*idx].bulk += traverser.bulk;
+                continue;
+            }
+            candidates.push(merged.len());
+        }
+        merged.push(traverser);
+    }
+    merged
+}
+
+/// Whether the traversers of identical heads can be merged into one.
+fn is_mergeable(t1: &Traverser, t2: &Traverser) -> bool {
+    t1.sack == t2.sack && t1.loops == t2.loops
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compiler::GremlinJobCompiler;
+    use crate::generated::gremlin as pb;
+    use crate::structure::{DefaultDetails, Label, Vertex};
+    use crate::{Element, Partition, ID};
+    use pegasus::api::Sink;
+    use pegasus::{Configuration, JobConf, StartupError};
+    use pegasus_server::pb as server_pb;
+    use prost::Message;
+
+    fn vertex_traverser(id: ID) -> Traverser {
+        let v = Vertex::new(id, Some(Label::Id(0)), DefaultDetails::new(id, Label::Id(0)));
+        Traverser::new(v)
+    }
+
+    #[test]
+    fn bulk_identity_test() {
+        match pegasus::startup(Configuration::singleton()) {
+            Ok(_) | Err(StartupError::AlreadyStarted(_)) => {}
+            _ => panic!("start pegasus failed"),
+        }
+        let identity = pb::GremlinStep {
+            step: Some(pb::gremlin_step::Step::IdentityStep(pb::IdentityStep::default())),
+            ..Default::default()
+        };
+        let map = server_pb::Map { resource: identity.encode_to_vec() };
+        let plan = vec![server_pb::OperatorDef {
+            op_kind: Some(server_pb::operator_def::OpKind::Map(map)),
+        }];
+        let conf = JobConf::new("bulk_identity_test");
+        let result = pegasus::run(conf, || {
+            let compiler = GremlinJobCompiler::new(Partition { num_servers: 1 }, 1, 0);
+            let plan = plan.clone();
+            move |input, output| {
+                let src = (0..1000).map(|_| vertex_traverser(1));
+                compiler
+                    .install_with(input.input_from(src)?, &plan, Requirement::BULK)?
+                    .sink_into(output)
+            }
+        })
+        .expect("submit job failure");
+        let merged: Vec<Traverser> = result.map(|t| t.unwrap()).collect();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].bulk, 1000);
+        assert_eq!(merged[0].get_element().unwrap().id(), 1);
+    }
+
+    #[test]
+    fn bulk_merge_order_test() {
+        let mut bulked = vertex_traverser(2);
+        bulked.bulk = 3;
+        let traversers = vec![vertex_traverser(2), vertex_traverser(1), bulked];
+        let merged = merge_bulk(traversers, Requirement::BULK);
+        let ids_and_bulks =
+            merged.iter().map(|t| (t.get_element().unwrap().id(), t.bulk)).collect::<Vec<_>>();
+        assert_eq!(ids_and_bulks, vec![(2, 4), (1, 1)]);
+    }
+
+    #[test]
+    fn bulk_merge_sack_test() {
+        let mut with_sack = vertex_traverser(1);
+        with_sack.sack = Some(0.into());
+        let traversers = vec![vertex_traverser(1), with_sack.clone(), with_sack];
+        let merged = merge_bulk(traversers, Requirement::BULK);
+        let bulks = merged.iter().map(|t| t.bulk).collect::<Vec<_>>();
+        assert_eq!(bulks, vec![1, 2]);
+    }
+}
@@ -0,0 +1,123 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use crate::process::traversal::traverser::{Requirement, Traverser};
+use pegasus::api::function::{FnResult, MapFunction};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LoopOp {
+    /// Enter a loop with the counter starting from 0
+    Start,
+    /// Finish an iteration of the innermost loop
+    Incr,
+    /// Leave the innermost loop
+    End,
+}
+
+/// Maintain the loop counters `Traverser::loops` for `repeat()`. The counters are stacked up for the
+/// nested loops if `Requirement::NESTED_LOOP` is set, otherwise, a single counter is kept if
+/// `Requirement::SINGLE_LOOP` is set. It does nothing if neither is set.
+pub struct LoopStep {
+    op: LoopOp,
+    requirement: Requirement,
+}
+
+impl LoopStep {
+    pub fn new(op: LoopOp, requirement: Requirement) -> Self {
+        LoopStep { op, requirement }
+    }
+}
+
+impl MapFunction<Traverser, Traverser> for LoopStep {
+    fn exec(&self, mut input: Traverser) -> FnResult<Traverser> {
+        let nested = self.requirement.contains(Requirement::NESTED_LOOP);
+        if !nested && !self.requirement.contains(Requirement::SINGLE_LOOP) {
+            return Ok(input);
+        }
+        match self.op {
+            LoopOp::Start => {
+                if !nested {
+                    input.loops.clear();
+                }
+                input.loops.push(0);
+            }
+            LoopOp::Incr => {
+                // a traverser created in the loop, e.g., by `count()`, enters the loop here
+                if let Some(loops) = input.loops.last_mut() {
+                    *loops += 1;
+                } else {
+                    input.loops.push(1);
+                }
+            }
+            LoopOp::End => {
+                input.loops.pop();
+            }
+        }
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn repeat_times(
+        input: Traverser, times: u32, requirement: Requirement,
+    ) -> (Traverser, Vec<u32>) {
+        let (start, incr, end) = (
+            LoopStep::new(LoopOp::Start, requirement),
+            LoopStep::new(LoopOp::Incr, requirement),
+            LoopStep::new(LoopOp::End, requirement),
+        );
+        let mut loops = vec![];
+        let mut traverser = start.exec(input).unwrap();
+        loop {
+            traverser = incr.exec(traverser).unwrap();
+            loops.push(traverser.get_loops());
+            if traverser.get_loops() == times {
+                break;
+            }
+        }
+        (end.exec(traverser).unwrap(), loops)
+    }
+
+    #[test]
+    fn single_loop_test() {
+        let (output, loops) = repeat_times(Traverser::object(1), 3, Requirement::SINGLE_LOOP);
+        assert_eq!(loops, vec![1, 2, 3]);
+        assert!(output.loops.is_empty());
+    }
+
+    #[test]
+    fn nested_loop_test() {
+        let requirement = Requirement::NESTED_LOOP;
+        let outer = LoopStep::new(LoopOp::Start, requirement).exec(Traverser::object(1)).unwrap();
+        let outer = LoopStep::new(LoopOp::Incr, requirement).exec(outer).unwrap();
+        let (output, loops) = repeat_times(outer, 2, requirement);
+        assert_eq!(loops, vec![1, 2]);
+        // back to the outer loop
+        assert_eq!(output.loops, vec![1]);
+        assert_eq!(output.get_loops(), 1);
+    }
+
+    #[test]
+    fn loop_not_required_test() {
+        let output =
+            LoopStep::new(LoopOp::Start, Requirement::empty()).exec(Traverser::object(1)).unwrap();
+        assert!(output.loops.is_empty());
+        let output = LoopStep::new(LoopOp::Incr, Requirement::empty()).exec(output).unwrap();
+        assert_eq!(output.get_loops(), 0);
+    }
+}
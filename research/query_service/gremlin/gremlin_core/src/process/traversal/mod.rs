@@ -13,7 +13,10 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+pub mod bulk;
+pub mod loops;
 pub mod path;
 pub mod pop;
+pub mod sack;
 pub mod step;
 pub mod traverser;
@@ -0,0 +1,161 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use crate::generated::gremlin as pb;
+use crate::process::traversal::step::MapFuncGen;
+use crate::process::traversal::traverser::{Requirement, Traverser};
+use crate::structure::{Details, Element, PropKey};
+use crate::{str_to_dyn_error, DynResult, FromPb};
+use dyn_type::Object;
+use pegasus::api::function::{FnResult, MapFunction};
+
+/// The sack value of a traverser, e.g., `g.withSack(0)`, which is carried when `Requirement::SACK`
+/// is set.
+pub type SackValue = Object;
+
+/// The sack step, e.g., `sack(sum).by("age")`, which updates the sack by `func` given the current
+/// sack and the traverser. It does nothing if `Requirement::SACK` is not set.
+pub struct SackStep<F> {
+    func: F,
+    requirement: Requirement,
+}
+
+impl<F> SackStep<F> {
+    pub fn new(func: F, requirement: Requirement) -> Self {
+        SackStep { func, requirement }
+    }
+}
+
+impl<F> MapFunction<Traverser, Traverser> for SackStep<F>
+where
+    F: Fn(Option<&SackValue>, &Traverser) -> FnResult<SackValue> + Send + 'static,
+{
+    fn exec(&self, mut input: Traverser) -> FnResult<Traverser> {
+        if self.requirement.contains(Requirement::SACK) {
+            let sack = (self.func)(input.sack.as_ref(), &input)?;
+            input.sack = Some(sack);
+        }
+        Ok(input)
+    }
+}
+
+impl MapFuncGen for pb::SackStep {
+    fn gen_map(self) -> DynResult<Box<dyn MapFunction<Traverser, Traverser>>> {
+        let operator = pb::sack_step::Operator::from_i32(self.operator)
+            .ok_or(str_to_dyn_error("invalid operator in SackStep"))?;
+        let by_key = self.by_key.map(PropKey::from_pb).transpose()?;
+        let requirements_pb = unsafe { std::mem::transmute(self.traverser_requirements) };
+        let requirement = Requirement::from_pb(requirements_pb)?;
+        let func = move |sack: Option<&SackValue>, input: &Traverser| -> FnResult<SackValue> {
+            let value = sack_value_of(input, by_key.as_ref())?;
+            match sack {
+                Some(sack) => apply_sack_operator(operator, sack, value),
+                // the first value assigns the sack, whatever the operator is
+                None => Ok(value),
+            }
+        };
+        Ok(Box::new(SackStep::new(func, requirement)))
+    }
+}
+
+fn sack_value_of(input: &Traverser, by_key: Option<&PropKey>) -> FnResult<SackValue> {
+    let value = if let Some(key) = by_key {
+        input
+            .get_element()
+            .and_then(|element| element.details().get_property(key))
+            .map(Object::from)
+    } else {
+        input.get_object().cloned()
+    };
+    value.ok_or(str_to_dyn_error("value of sack is not found"))
+}
+
+fn apply_sack_operator(
+    operator: pb::sack_step::Operator, sack: &SackValue, value: SackValue,
+) -> FnResult<SackValue> {
+    let as_primitive =
+        |o: &Object| o.as_primitive().map_err(|e| str_to_dyn_error(&format!("{}", e)));
+    match operator {
+        pb::sack_step::Operator::Assign => Ok(value),
+        pb::sack_step::Operator::Sum => {
+            Ok(Object::Primitive(as_primitive(sack)? + as_primitive(&value)?))
+        }
+        pb::sack_step::Operator::Mult => {
+            Ok(Object::Primitive(as_primitive(sack)? * as_primitive(&value)?))
+        }
+        pb::sack_step::Operator::Min => Ok(if value < *sack { value } else { sack.clone() }),
+        pb::sack_step::Operator::Max => Ok(if value > *sack { value } else { sack.clone() }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn incr(sack: Option<&SackValue>, _: &Traverser) -> FnResult<SackValue> {
+        let sack = sack.ok_or(str_to_dyn_error("sack is not initialized"))?;
+        let value = sack.as_i32().map_err(|e| str_to_dyn_error(&format!("{}", e)))?;
+        Ok((value + 1).into())
+    }
+
+    fn with_sack(sack: i32) -> Traverser {
+        let mut traverser = Traverser::object(1);
+        traverser.sack = Some(sack.into());
+        traverser
+    }
+
+    #[test]
+    fn sack_incr_test() {
+        let step = SackStep::new(incr, Requirement::SACK);
+        let output = step.exec(step.exec(with_sack(0)).unwrap()).unwrap();
+        assert_eq!(output.sack, Some(Object::from(2)));
+        assert_eq!(output.get_object(), Some(&Object::from(1)));
+        // the sack is cloned along with the traverser, e.g., by the flat_map steps
+        assert_eq!(output.clone().sack, Some(Object::from(2)));
+    }
+
+    #[test]
+    fn sack_step_gen_test() {
+        let sack_step = |operator: pb::sack_step::Operator| {
+            pb::SackStep {
+                operator: operator as i32,
+                by_key: None,
+                traverser_requirements: vec![pb::TraverserRequirement::Sack as i32],
+            }
+            .gen_map()
+            .unwrap()
+        };
+        let (sum, mult) =
+            (sack_step(pb::sack_step::Operator::Sum), sack_step(pb::sack_step::Operator::Mult));
+        let output = sum.exec(Traverser::object(3)).unwrap();
+        assert_eq!(output.sack, Some(Object::from(3)));
+        let output = sum.exec(output).unwrap();
+        assert_eq!(output.sack, Some(Object::from(6)));
+        let output = mult.exec(output).unwrap();
+        assert_eq!(output.sack, Some(Object::from(18)));
+
+        let output = sack_step(pb::sack_step::Operator::Max).exec(with_sack(5)).unwrap();
+        assert_eq!(output.sack, Some(Object::from(5)));
+        let output = sack_step(pb::sack_step::Operator::Min).exec(output).unwrap();
+        assert_eq!(output.sack, Some(Object::from(1)));
+    }
+
+    #[test]
+    fn sack_not_required_test() {
+        let step = SackStep::new(incr, Requirement::empty());
+        let output = step.exec(with_sack(0)).unwrap();
+        assert_eq!(output.sack, Some(Object::from(0)));
+    }
+}
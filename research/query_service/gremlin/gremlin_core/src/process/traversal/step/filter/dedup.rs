@@ -0,0 +1,66 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use crate::process::traversal::traverser::Traverser;
+use crate::structure::Tag;
+use crate::{str_to_dyn_error, Element, ID};
+use pegasus::api::Unary;
+use pegasus::stream::Stream;
+use pegasus::tag::tools::map::TidyTagMap;
+use pegasus::BuildJobError;
+use std::collections::HashSet;
+
+/// dedup(), or dedup("a") by the element of tag "a", which keeps the first traverser of each element
+/// in a scope, where the elements are compared by their ids. It is a stateful operator, where the
+/// elements seen in a scope are kept until the end of the scope arrives, so a scope never affects
+/// others. The traversers of the same element are expected to be routed to the same worker.
+pub struct DedupStep {
+    tag: Option<Tag>,
+}
+
+impl DedupStep {
+    pub fn new(tag: Option<Tag>) -> Self {
+        DedupStep { tag }
+    }
+
+    /// Install the step as an operator on `stream`.
+    pub fn install(self, stream: Stream<Traverser>) -> Result<Stream<Traverser>, BuildJobError> {
+        stream.unary("dedup", |info| {
+            let mut table = TidyTagMap::<HashSet<ID>>::new(info.scope_level);
+            move |input, output| {
+                input.for_each_batch(|batch| {
+                    if !batch.is_empty() {
+                        let mut session = output.new_session(&batch.tag)?;
+                        let seen = table.get_mut_or_insert(&batch.tag);
+                        for t in batch.drain() {
+                            let element = t
+                                .select_as_element(self.tag.as_ref())
+                                .ok_or(str_to_dyn_error("invalid input for dedup"))?;
+                            if seen.insert(element.id()) {
+                                session.give(t)?;
+                            }
+                        }
+                    }
+
+                    if batch.is_last() {
+                        table.remove(&batch.tag);
+                    }
+
+                    Ok(())
+                })
+            }
+        })
+    }
+}
@@ -18,9 +18,12 @@ use crate::process::traversal::traverser::Traverser;
 use crate::{str_to_dyn_error, DynResult};
 use pegasus::api::function::FilterFunction;
 
+mod dedup;
 mod has;
 mod where_predicate;
 
+pub use dedup::DedupStep;
+
 #[enum_dispatch]
 pub trait FilterFuncGen {
     fn gen_filter(self) -> DynResult<Box<dyn FilterFunction<Traverser>>>;
@@ -13,10 +13,12 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use super::FlatMapFuncGen;
+use super::{FlatMapFuncGen, TraverserFlatMap};
 use crate::generated::gremlin as pb;
 use crate::process::traversal::traverser::{Traverser, TraverserSplitIter};
-use crate::structure::{Direction, Element, GraphElement, QueryParams, Statement, ID};
+use crate::structure::{
+    limit_statement, Direction, Element, GraphCapability, GraphElement, QueryParams, Statement, ID,
+};
 use crate::{str_to_dyn_error, DynIter, DynResult, FromPb};
 use bit_set::BitSet;
 use pegasus::api::function::FlatMapFunction;
@@ -27,16 +29,16 @@ pub struct FlatMapStatement<E: Into<GraphElement>> {
     stmt: Box<dyn Statement<ID, E>>,
 }
 
-impl<E: Into<GraphElement> + 'static> FlatMapFunction<Traverser, Traverser>
+impl<E: Into<GraphElement> + Send + 'static> FlatMapFunction<Traverser, DynResult<Traverser>>
     for FlatMapStatement<E>
 {
-    type Target = DynIter<Traverser>;
+    type Target = DynIter<DynResult<Traverser>>;
 
-    fn exec(&self, input: Traverser) -> DynResult<DynIter<Traverser>> {
+    fn exec(&self, input: Traverser) -> DynResult<DynIter<DynResult<Traverser>>> {
         if let Some(e) = input.get_element() {
             let id = e.id();
-            let iter = self.stmt.exec(id)?;
-            Ok(Box::new(TraverserSplitIter::new(input, &self.tags, iter)))
+            let iter = self.stmt.try_exec(id)?;
+            Ok(Box::new(TraverserSplitIter::new(input, &self.tags, Box::new(iter))))
         } else {
             Err(str_to_dyn_error("invalid input for vertex/edge step"))
         }
@@ -50,21 +52,22 @@ pub struct VertexStep {
 }
 
 impl FlatMapFuncGen for VertexStep {
-    fn gen_flat_map(
-        self,
-    ) -> DynResult<Box<dyn FlatMapFunction<Traverser, Traverser, Target = DynIter<Traverser>>>>
-    {
+    fn gen_flat_map(self) -> DynResult<TraverserFlatMap> {
         let step = self.step;
         let direction_pb = unsafe { std::mem::transmute(step.direction) };
         let direction = Direction::from_pb(direction_pb)?;
         let graph = crate::get_graph().ok_or(str_to_dyn_error("Graph is None"))?;
+        // the limit is applied in the runtime unless the storage applies it for each input
+        let limit_pushdown = graph.supports(GraphCapability::LimitPushdown);
         if step.return_type == 0 {
             let params = QueryParams::from_pb(step.query_params)?;
-            let stmt = graph.prepare_explore_vertex(direction, &params)?;
+            let limit = if limit_pushdown { None } else { params.limit };
+            let stmt = limit_statement(graph.prepare_explore_vertex(direction, &params)?, limit);
             Ok(Box::new(FlatMapStatement { tags: Arc::new(self.tags), stmt }))
         } else if step.return_type == 1 {
             let params = QueryParams::from_pb(step.query_params)?;
-            let stmt = graph.prepare_explore_edge(direction, &params)?;
+            let limit = if limit_pushdown { None } else { params.limit };
+            let stmt = limit_statement(graph.prepare_explore_edge(direction, &params)?, limit);
             Ok(Box::new(FlatMapStatement { tags: Arc::new(self.tags), stmt }))
         } else {
             Err(str_to_dyn_error("Wrong return type in VertexStep"))
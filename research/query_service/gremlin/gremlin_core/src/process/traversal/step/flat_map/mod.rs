@@ -19,25 +19,34 @@ use crate::process::traversal::step::flat_map::values::PropertiesStep;
 use crate::process::traversal::step::Step;
 use crate::process::traversal::traverser::Traverser;
 use crate::structure::PropKey;
-use crate::{str_to_dyn_error, DynResult, FromPb};
-use pegasus::api::function::{DynIter, FlatMapFunction};
+use crate::{str_to_dyn_error, DynError, DynResult, FromPb};
+use pegasus::api::function::{DynIter, FlatMapFunction, FnResult};
+use pegasus::api::Unary;
+use pegasus::stream::Stream;
+use pegasus::tag::tools::map::TidyTagMap;
+use pegasus::BuildJobError;
+use std::marker::PhantomData;
+use std::sync::Arc;
 
 mod explore;
 mod unfold;
 mod values;
 
+pub use values::{PropertyElement, PropertyElementsStep};
+
+/// A flat_map step, whose outputs may fail during the iteration, e.g., a storage read fails
+/// mid-stream, where the error is returned in place of the output it fails on.
+pub type TraverserFlatMap = Box<
+    dyn FlatMapFunction<Traverser, DynResult<Traverser>, Target = DynIter<DynResult<Traverser>>>,
+>;
+
 #[enum_dispatch]
 pub trait FlatMapFuncGen {
-    fn gen_flat_map(
-        self,
-    ) -> DynResult<Box<dyn FlatMapFunction<Traverser, Traverser, Target = DynIter<Traverser>>>>;
+    fn gen_flat_map(self) -> DynResult<TraverserFlatMap>;
 }
 
 impl FlatMapFuncGen for pb::GremlinStep {
-    fn gen_flat_map(
-        self,
-    ) -> DynResult<Box<dyn FlatMapFunction<Traverser, Traverser, Target = DynIter<Traverser>>>>
-    {
+    fn gen_flat_map(self) -> DynResult<TraverserFlatMap> {
         let tags = self.get_tags();
 
         if let Some(step) = self.step {
@@ -47,15 +56,26 @@ impl FlatMapFuncGen for pb::GremlinStep {
                     vertex_step.gen_flat_map()
                 }
                 pb::gremlin_step::Step::PropertiesStep(properties_step) => {
+                    let return_type = pb::PropertyType::from_i32(properties_step.return_type)
+                        .ok_or(str_to_dyn_error("invalid return type in PropertiesStep"))?;
                     let mut prop_keys= vec![] ;
                     if let Some(prop_step_keys) = properties_step.prop_keys {
                         for prop_key in prop_step_keys.prop_keys {
                             prop_keys.push(PropKey::from_pb(prop_key)?);
                         }
                     };
-                    Ok(Box::new(PropertiesStep { prop_keys, tags }))
+                    match return_type {
+                        pb::PropertyType::Value => {
+                            Ok(Box::new(Infallible(PropertiesStep { prop_keys, tags })))
+                        }
+                        pb::PropertyType::Property => {
+                            Ok(Box::new(Infallible(PropertyElementsStep { prop_keys, tags })))
+                        }
+                    }
+                }
+                pb::gremlin_step::Step::UnfoldStep(unfold_step) => {
+                    Ok(Box::new(Infallible(unfold_step)))
                 }
-                pb::gremlin_step::Step::UnfoldStep(unfold_step) => Ok(Box::new(unfold_step)),
                 _ => Err(str_to_dyn_error("pb GremlinStep is not a FlatMap Step")),
             }
         } else {
@@ -63,3 +83,320 @@ impl FlatMapFuncGen for pb::GremlinStep {
         }
     }
 }
+
+/// A flat_map step whose outputs never fail, adapted to a `TraverserFlatMap`.
+struct Infallible<F>(F);
+
+impl<F: FlatMapFunction<Traverser, Traverser>> FlatMapFunction<Traverser, DynResult<Traverser>>
+    for Infallible<F>
+{
+    type Target = DynIter<DynResult<Traverser>>;
+
+    fn exec(&self, input: Traverser) -> DynResult<Self::Target> {
+        Ok(Box::new(self.0.exec(input)?.map(Ok)))
+    }
+}
+
+/// Install `func` as a flat_map operator on `stream`, where the outputs of each input are consumed
+/// lazily, and the job fails on the first output that is an error. The outputs left when the output
+/// of the operator is blocked are kept, and resumed once the operator is scheduled again.
+pub fn install_flat_map(
+    stream: Stream<Traverser>, func: TraverserFlatMap,
+) -> Result<Stream<Traverser>, BuildJobError> {
+    stream.unary("flat_map", |info| {
+        let mut blocked = TidyTagMap::<DynIter<DynResult<Traverser>>>::new(info.scope_level);
+        move |input, output| {
+            input.for_each_batch(|batch| {
+                let mut outputs = blocked.remove(&batch.tag);
+                if batch.is_empty() && outputs.is_none() {
+                    return Ok(());
+                }
+                let mut session = output.new_session(&batch.tag)?;
+                loop {
+                    let mut current = match outputs.take() {
+                        Some(current) => current,
+                        None => match batch.drain().next() {
+                            Some(t) => func.exec(t)?,
+                            None => return Ok(()),
+                        },
+                    };
+                    while let Some(t) = current.next() {
+                        if let Err(err) = session.give(t?) {
+                            if err.is_would_block() || err.is_interrupted() {
+                                blocked.insert(batch.tag.clone(), current);
+                            }
+                            return Err(err)?;
+                        }
+                    }
+                }
+            })
+        }
+    })
+}
+
+/// Compose two flat_map steps, e.g., `out().out()`, into one, where the `second` is applied on each
+/// output of the `first` lazily, i.e., one at a time as the outputs are consumed, so that the
+/// intermediate outputs are never materialized. An error of the `first` on the input is returned by
+/// `exec`, while an error met after `exec` returns, i.e., an error output of the `first`, or an error
+/// of the `second`, is returned in place of the output it fails on, which stops the outputs.
+///
+/// The fused step iterates the outputs of the `second` directly, rather than boxing the flattened
+/// outputs of each stage into another `DynIter`, which saves a box and a dynamic dispatch per stage.
+/// Note that the outputs of the `second` are still boxed if its `Target` is, e.g., a `DynIter` as
+/// of the gremlin steps.
+pub fn fuse_flat_maps<I, M, O, F1, F2>(first: F1, second: F2) -> FusedFlatMap<F1, F2, M>
+where
+    F1: FlatMapFunction<I, FnResult<M>>,
+    F2: FlatMapFunction<M, FnResult<O>> + Sync,
+{
+    FusedFlatMap { first, second: Arc::new(second), _ph: PhantomData }
+}
+
+pub struct FusedFlatMap<F1, F2, M> {
+    first: F1,
+    // shared with the outputs, which call the `second` lazily
+    second: Arc<F2>,
+    _ph: PhantomData<fn(M) -> M>,
+}
+
+impl<I, M, O, F1, F2> FlatMapFunction<I, FnResult<O>> for FusedFlatMap<F1, F2, M>
+where
+    M: 'static,
+    O: 'static,
+    F1: FlatMapFunction<I, FnResult<M>>,
+    F2: FlatMapFunction<M, FnResult<O>> + Sync,
+{
+    type Target = FusedIter<F1::Target, F2, M, O>;
+
+    fn exec(&self, input: I) -> FnResult<Self::Target> {
+        let firsts = self.first.exec(input)?;
+        Ok(FusedIter {
+            firsts: Some(firsts),
+            second: self.second.clone(),
+            current: None,
+            _ph: PhantomData,
+        })
+    }
+}
+
+/// The outputs of `FusedFlatMap`, which stop at the first error.
+pub struct FusedIter<T, F2: FlatMapFunction<M, FnResult<O>>, M, O> {
+    firsts: Option<T>,
+    second: Arc<F2>,
+    current: Option<F2::Target>,
+    _ph: PhantomData<fn(M) -> O>,
+}
+
+impl<T, F2: FlatMapFunction<M, FnResult<O>>, M, O> FusedIter<T, F2, M, O> {
+    fn fail(&mut self, e: DynError) -> Option<FnResult<O>> {
+        self.firsts = None;
+        self.current = None;
+        Some(Err(e))
+    }
+}
+
+impl<T, F2, M, O> Iterator for FusedIter<T, F2, M, O>
+where
+    T: Iterator<Item = FnResult<M>>,
+    F2: FlatMapFunction<M, FnResult<O>>,
+{
+    type Item = FnResult<O>;
+
+    fn next(&mut self) -> Option<FnResult<O>> {
+        loop {
+            match self.current.as_mut().and_then(|outputs| outputs.next()) {
+                Some(Ok(o)) => return Some(Ok(o)),
+                Some(Err(e)) => return self.fail(e),
+                None => {}
+            }
+            let mid = match self.firsts.as_mut()?.next()? {
+                Ok(mid) => mid,
+                Err(e) => return self.fail(e),
+            };
+            match self.second.exec(mid) {
+                Ok(outputs) => self.current = Some(outputs),
+                Err(e) => return self.fail(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dyn_type::Object;
+    use pegasus::api::Sink;
+    use pegasus::{Configuration, JobConf, StartupError};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Expand an object `x` into `x * 10 + i` for `i` in `0..fanout`
+    struct Expand {
+        fanout: i64,
+    }
+
+    impl FlatMapFunction<Traverser, Traverser> for Expand {
+        type Target = DynIter<Traverser>;
+
+        fn exec(&self, input: Traverser) -> FnResult<Self::Target> {
+            let x = input.get_object().unwrap().as_i64().unwrap();
+            Ok(Box::new((0..self.fanout).map(move |i| Traverser::object(x * 10 + i))))
+        }
+    }
+
+    /// Count the calls, and expand as `Expand`
+    struct Counted {
+        calls: Arc<AtomicUsize>,
+        fanout: i64,
+    }
+
+    impl FlatMapFunction<Traverser, Traverser> for Counted {
+        type Target = DynIter<Traverser>;
+
+        fn exec(&self, input: Traverser) -> FnResult<Self::Target> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Expand { fanout: self.fanout }.exec(input)
+        }
+    }
+
+    /// Fail on the objects greater than `max`, and output the others as is
+    struct FailOver {
+        max: i64,
+    }
+
+    impl FlatMapFunction<Traverser, Traverser> for FailOver {
+        type Target = DynIter<Traverser>;
+
+        fn exec(&self, input: Traverser) -> FnResult<Self::Target> {
+            if input.get_object().unwrap().as_i64().unwrap() > self.max {
+                Err(str_to_dyn_error("too large"))
+            } else {
+                Ok(Box::new(std::iter::once(input)))
+            }
+        }
+    }
+
+    /// Expand as `Expand`, while the output of `i == fail_at` is an error
+    struct FailAt {
+        fanout: i64,
+        fail_at: i64,
+    }
+
+    impl FlatMapFunction<Traverser, DynResult<Traverser>> for FailAt {
+        type Target = DynIter<DynResult<Traverser>>;
+
+        fn exec(&self, input: Traverser) -> FnResult<Self::Target> {
+            let x = input.get_object().unwrap().as_i64().unwrap();
+            let fail_at = self.fail_at;
+            Ok(Box::new((0..self.fanout).map(move |i| {
+                if i == fail_at {
+                    Err(str_to_dyn_error("storage read failure"))
+                } else {
+                    Ok(Traverser::object(x * 10 + i))
+                }
+            })))
+        }
+    }
+
+    fn run_flat_map(name: &str, fanout: i64, fail_at: i64) -> Vec<FnResult<Traverser>> {
+        match pegasus::startup(Configuration::singleton()) {
+            Ok(_) | Err(StartupError::AlreadyStarted(_)) => {}
+            _ => panic!("start pegasus failed"),
+        }
+        let conf = JobConf::new(name);
+        let results = pegasus::run(conf, || {
+            move |input, output| {
+                let src = vec![Traverser::object(1)].into_iter();
+                let func = Box::new(FailAt { fanout, fail_at });
+                install_flat_map(input.input_from(src)?, func)?.sink_into(output)
+            }
+        })
+        .expect("submit job failure");
+        results.collect()
+    }
+
+    #[test]
+    fn install_flat_map_test() {
+        let outputs = run_flat_map("install_flat_map_test", 5, 5);
+        let outputs: Vec<Object> =
+            outputs.into_iter().map(|t| t.unwrap().get_object().unwrap().clone()).collect();
+        let expected: Vec<Object> = (10..15).map(|x: i64| x.into()).collect();
+        assert_eq!(outputs, expected);
+        // the error met in the middle of the outputs fails the job
+        let outputs = run_flat_map("install_flat_map_error_test", 5, 2);
+        assert!(outputs.iter().any(|t| t.is_err()));
+    }
+
+    #[test]
+    fn fuse_flat_maps_test() {
+        let fused = fuse_flat_maps(
+            fuse_flat_maps(Infallible(Expand { fanout: 2 }), Infallible(Expand { fanout: 2 })),
+            Infallible(Expand { fanout: 2 }),
+        );
+        let outputs: Vec<Object> = fused
+            .exec(Traverser::object(1))
+            .unwrap()
+            .map(|t| t.unwrap().get_object().unwrap().clone())
+            .collect();
+        let expected: Vec<Object> = vec![1000, 1001, 1010, 1011, 1100, 1101, 1110, 1111]
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+        assert_eq!(outputs, expected);
+
+        // the fused step is equivalent to the chained steps
+        let fused = fuse_flat_maps(
+            fuse_flat_maps(Infallible(Expand { fanout: 3 }), Infallible(Expand { fanout: 4 })),
+            Infallible(Expand { fanout: 5 }),
+        );
+        let (first, second, third) =
+            (Expand { fanout: 3 }, Expand { fanout: 4 }, Expand { fanout: 5 });
+        for x in 0..1000 {
+            let fused_outputs: Vec<Traverser> =
+                fused.exec(Traverser::object(x)).unwrap().map(|t| t.unwrap()).collect();
+            let chained_outputs: Vec<Traverser> = first
+                .exec(Traverser::object(x))
+                .unwrap()
+                .flat_map(|t| second.exec(t).unwrap())
+                .flat_map(|t| third.exec(t).unwrap())
+                .collect();
+            assert_eq!(fused_outputs.len(), 60);
+            assert_eq!(fused_outputs, chained_outputs);
+        }
+
+        // the error of the `first` on the input is returned by `exec`
+        let fused =
+            fuse_flat_maps(Infallible(FailOver { max: 1 }), Infallible(Expand { fanout: 3 }));
+        assert!(fused.exec(Traverser::object(2)).is_err());
+        // while the error of the `second` is returned in place of the output, and stops the outputs
+        let fused =
+            fuse_flat_maps(Infallible(Expand { fanout: 3 }), Infallible(FailOver { max: 21 }));
+        assert_eq!(fused.exec(Traverser::object(1)).unwrap().count(), 3);
+        let outputs: Vec<FnResult<Traverser>> = fused.exec(Traverser::object(2)).unwrap().collect();
+        assert_eq!(outputs.len(), 3);
+        assert!(outputs[0].is_ok() && outputs[1].is_ok());
+        match &outputs[2] {
+            Err(e) => assert_eq!(e.to_string(), "too large"),
+            Ok(_) => panic!("the error of the `second` is not returned"),
+        }
+        // as is an error output of the `first`
+        let fused =
+            fuse_flat_maps(FailAt { fanout: 3, fail_at: 1 }, Infallible(Expand { fanout: 2 }));
+        let outputs: Vec<FnResult<Traverser>> = fused.exec(Traverser::object(1)).unwrap().collect();
+        assert_eq!(outputs.len(), 3);
+        assert!(outputs[2].is_err());
+    }
+
+    #[test]
+    fn fuse_flat_maps_lazy_test() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fused = fuse_flat_maps(
+            Infallible(Expand { fanout: 1000 }),
+            Infallible(Counted { calls: calls.clone(), fanout: 2 }),
+        );
+        let outputs: Vec<FnResult<Traverser>> =
+            fused.exec(Traverser::object(1)).unwrap().take(3).collect();
+        assert_eq!(outputs.len(), 3);
+        // the `second` is called only on the outputs of the `first` consumed
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -16,25 +16,68 @@
 use crate::generated::gremlin as pb;
 use crate::process::traversal::step::util::result_downcast::try_downcast_list;
 use crate::process::traversal::traverser::Traverser;
-use crate::{str_to_dyn_error, DynResult};
+use crate::DynResult;
 use pegasus::api::function::{DynIter, FlatMapFunction};
 
 /// This unfold step is used in group().by().by(sub_traversal)
 /// When we process by(sub_traversal) on the result of group().by(), which is a pair of (traverser, Vec<Traverser>),
 /// We need to get_values which is a list, and unfold it first
-
+///
+/// A traverser whose head is a list emits one traverser per element (none for an empty list),
+/// and a traverser with a scalar head, e.g., a graph element or a primitive value, passes through unchanged.
+/// There is no map-valued head to unfold, as a map, e.g., the result of group(), is emitted as one
+/// (key, value) pair per entry already, and such an entry passes through unchanged as in Gremlin.
 impl FlatMapFunction<Traverser, Traverser> for pb::UnfoldStep {
     type Target = DynIter<Traverser>;
 
     fn exec(&self, input: Traverser) -> DynResult<DynIter<Traverser>> {
-        let list_traverser = try_downcast_list(
-            input
-                .get_object()
-                .ok_or(str_to_dyn_error("The input traverser for fold should be object"))?,
-        )
-        .ok_or(str_to_dyn_error("Try downcast object for unfold failed"))?
-        .into_iter()
-        .map(|trav| trav);
-        Ok(Box::new(list_traverser))
+        if let Some(list_traverser) = input.get_object().and_then(try_downcast_list) {
+            Ok(Box::new(list_traverser.into_iter()))
+        } else {
+            Ok(Box::new(std::iter::once(input)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::process::traversal::step::util::result_downcast::try_downcast_pair;
+    use dyn_type::Object;
+
+    fn unfold(input: Traverser) -> Vec<Traverser> {
+        pb::UnfoldStep {}.exec(input).unwrap().collect()
+    }
+
+    #[test]
+    fn unfold_list_test() {
+        let list: Vec<Traverser> =
+            vec!["a", "b", "c"].into_iter().map(|s| Traverser::object(s)).collect();
+        let result = unfold(Traverser::with(list));
+        let heads: Vec<&Object> = result.iter().map(|t| t.get_object().unwrap()).collect();
+        assert_eq!(heads, vec![&Object::from("a"), &Object::from("b"), &Object::from("c")]);
+    }
+
+    #[test]
+    fn unfold_empty_list_test() {
+        let list: Vec<Traverser> = vec![];
+        assert!(unfold(Traverser::with(list)).is_empty());
+    }
+
+    #[test]
+    fn unfold_scalar_test() {
+        let result = unfold(Traverser::object(1));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].get_object(), Some(&Object::from(1)));
+    }
+
+    #[test]
+    fn unfold_map_entry_test() {
+        let entry = (Traverser::object("a"), Traverser::object(1));
+        let result = unfold(Traverser::with(entry));
+        assert_eq!(result.len(), 1);
+        let entry = result[0].get_object().and_then(try_downcast_pair).unwrap();
+        assert_eq!(entry.0.get_object(), Some(&Object::from("a")));
+        assert_eq!(entry.1.get_object(), Some(&Object::from(1)));
     }
 }
@@ -17,7 +17,10 @@ use crate::process::traversal::traverser::Traverser;
 use crate::structure::{Details, PropKey};
 use crate::{str_to_dyn_error, DynIter, DynResult, Element};
 use bit_set::BitSet;
+use dyn_type::Object;
 use pegasus::api::function::FlatMapFunction;
+use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
+use std::io;
 
 pub struct PropertiesStep {
     pub prop_keys: Vec<PropKey>,
@@ -50,3 +53,110 @@ impl FlatMapFunction<Traverser, Traverser> for PropertiesStep {
         }
     }
 }
+
+/// A property of an element, i.e., the output of `properties()`, which keeps the key of the value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyElement {
+    pub key: PropKey,
+    pub value: Object,
+}
+
+impl Encode for PropertyElement {
+    fn write_to<W: WriteExt>(&self, writer: &mut W) -> io::Result<()> {
+        self.key.write_to(writer)?;
+        self.value.write_to(writer)
+    }
+}
+
+impl Decode for PropertyElement {
+    fn read_from<R: ReadExt>(reader: &mut R) -> io::Result<Self> {
+        let key = <PropKey>::read_from(reader)?;
+        let value = <Object>::read_from(reader)?;
+        Ok(PropertyElement { key, value })
+    }
+}
+
+/// properties(), or properties("name", "age") with the keys given, which outputs a `PropertyElement`
+/// for each property of the element, unlike `PropertiesStep` that outputs the values only.
+/// An absent property is skipped, thus an element without any of the properties outputs nothing.
+pub struct PropertyElementsStep {
+    pub prop_keys: Vec<PropKey>,
+    pub tags: BitSet,
+}
+
+impl FlatMapFunction<Traverser, Traverser> for PropertyElementsStep {
+    type Target = DynIter<Traverser>;
+
+    fn exec(&self, input: Traverser) -> DynResult<DynIter<Traverser>> {
+        let elem = input.get_element().ok_or(str_to_dyn_error("invalid input for properties;"))?;
+        let properties = if self.prop_keys.is_empty() {
+            elem.details()
+                .get_all_properties()
+                .ok_or(str_to_dyn_error("Can't get all properties of the element"))?
+        } else {
+            let mut properties = vec![];
+            for key in self.prop_keys.iter() {
+                if let Some(value) = elem.details().get_property(key) {
+                    let value = value
+                        .try_to_owned()
+                        .ok_or(str_to_dyn_error("Can't get owned property value"))?;
+                    properties.push((key.clone(), value));
+                }
+            }
+            properties
+        };
+        let mut result = Vec::with_capacity(properties.len());
+        for (key, value) in properties {
+            let mut traverser = input.clone();
+            let property = PropertyElement { key, value };
+            traverser.split_with_value(Object::DynOwned(Box::new(property)), &self.tags);
+            result.push(traverser);
+        }
+        Ok(Box::new(result.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structure::{DefaultDetails, Label, Vertex};
+
+    fn person() -> Traverser {
+        let mut details = DefaultDetails::new(1, Label::Str("person".to_owned()));
+        details.insert("name".into(), "marko".into());
+        details.insert("age".into(), 29.into());
+        Traverser::new(Vertex::new(1, None, details))
+    }
+
+    fn property_elements(step: &PropertyElementsStep, input: Traverser) -> Vec<PropertyElement> {
+        step.exec(input)
+            .unwrap()
+            .map(|t| match t.get_object() {
+                Some(Object::DynOwned(obj)) => {
+                    obj.try_downcast_ref::<PropertyElement>().unwrap().clone()
+                }
+                _ => panic!("the output is not a property element"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn property_elements_test() {
+        let age = PropertyElement { key: "age".into(), value: 29.into() };
+        let name = PropertyElement { key: "name".into(), value: "marko".into() };
+
+        let step = PropertyElementsStep { prop_keys: vec![], tags: BitSet::new() };
+        assert_eq!(property_elements(&step, person()), vec![age.clone(), name.clone()]);
+
+        let prop_keys = vec!["name".into(), "age".into()];
+        let step = PropertyElementsStep { prop_keys, tags: BitSet::new() };
+        assert_eq!(property_elements(&step, person()), vec![name.clone(), age]);
+
+        let prop_keys = vec!["name".into(), "weight".into()];
+        let step = PropertyElementsStep { prop_keys, tags: BitSet::new() };
+        assert_eq!(property_elements(&step, person()), vec![name]);
+
+        let step = PropertyElementsStep { prop_keys: vec!["weight".into()], tags: BitSet::new() };
+        assert!(property_elements(&step, person()).is_empty());
+    }
+}
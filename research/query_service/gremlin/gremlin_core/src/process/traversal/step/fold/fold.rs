@@ -68,7 +68,7 @@ impl Accumulator<Traverser, Traverser> for TraverserAccumulator {
 
     fn finalize(&mut self) -> Traverser {
         match self {
-            TraverserAccumulator::ToCount(count) => Traverser::Object(count.finalize().into()),
+            TraverserAccumulator::ToCount(count) => Traverser::object(count.finalize()),
             TraverserAccumulator::ToList(list) => Traverser::with(list.finalize()),
         }
     }
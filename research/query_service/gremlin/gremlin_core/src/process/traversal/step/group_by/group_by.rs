@@ -30,7 +30,7 @@ impl KeyFunction<Traverser, Traverser, Traverser> for KeyBy {
                             .try_to_owned()
                             .ok_or(str_to_dyn_error("Can't get owned property value"))?,
                     };
-                    Ok((Traverser::Object(obj), item))
+                    Ok((Traverser::object(obj), item))
                 }
                 // TODO: by select("a").by(valueMap("name")) or by(valueMap("name"))
                 ByStepOption::OptProperties(_) => {
@@ -49,7 +49,7 @@ impl KeyFunction<Traverser, Traverser, Traverser> for KeyBy {
                         .get_attached()
                         .ok_or(str_to_dyn_error("should with attached object"))?
                         .clone();
-                    Ok((Traverser::Object(obj), item))
+                    Ok((Traverser::object(obj), item))
                 }
             }
         } else {
@@ -59,13 +59,13 @@ impl KeyFunction<Traverser, Traverser, Traverser> for KeyBy {
                     .select_as_value(tag)
                     .ok_or(str_to_dyn_error("Select tag as value error!"))?
                     .clone();
-                Ok((Traverser::Object(obj), item))
+                Ok((Traverser::object(obj), item))
             } else {
                 // group by self, no need to keep path
                 if let Some(element) = item.get_element() {
                     Ok((Traverser::new(element.clone()), item))
                 } else if let Some(object) = item.get_object() {
-                    Ok((Traverser::Object(object.clone()), item))
+                    Ok((Traverser::object(object.clone()), item))
                 } else {
                     unreachable!()
                 }
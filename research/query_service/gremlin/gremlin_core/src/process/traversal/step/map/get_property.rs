@@ -28,6 +28,11 @@ use pegasus::api::function::*;
 use pegasus::codec::{Encode, WriteExt};
 use std::io;
 
+/// The multi-tag select step, e.g. `select("a", "b")`.
+///
+/// The output is a `ResultProperty` whose entries follow the order of the tags given in the pb,
+/// i.e., `select("b", "a")` always outputs the entry of "b" before "a". A tag that can not be
+/// found in the path of the traverser is an error, rather than an empty entry.
 struct SelectStep {
     tag_keys: Vec<TagKey>,
     pop: Pop,
@@ -166,7 +171,7 @@ impl MapFunction<Traverser, Traverser> for SelectStep {
                             input.get_object().ok_or(str_to_dyn_error("should with an object"))?
                         };
                         if let Some(count_value) = try_downcast_group_count_value(map_object) {
-                            return Ok(Traverser::Object(count_value.into()));
+                            return Ok(Traverser::object(count_value));
                         } else if let Some(traverser_value) = try_downcast_group_value(map_object) {
                             return Ok(traverser_value.clone());
                         } else {
@@ -201,7 +206,7 @@ impl MapFunction<Traverser, Traverser> for SelectStep {
                 Err(str_to_dyn_error("no tag is provided in select, should be unreachable"))?;
             }
         }
-        Ok(Traverser::Object(Object::DynOwned(Box::new(result))))
+        Ok(Traverser::object(Object::DynOwned(Box::new(result))))
     }
 }
 
@@ -257,3 +262,64 @@ impl MapFuncGen for pb::SelectStep {
         Ok(Box::new(SelectStep { tag_keys, pop }))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::process::traversal::traverser::Requirement;
+    use crate::structure::{DefaultDetails, Label, Vertex, ID};
+    use bit_set::BitSet;
+
+    fn select_step_pb(tags: Vec<i32>) -> pb::SelectStep {
+        let select_keys = tags
+            .into_iter()
+            .map(|tag| pb::TagKey {
+                tag: Some(pb::StepTag { item: Some(pb::step_tag::Item::Tag(tag)) }),
+                by_key: None,
+            })
+            .collect();
+        pb::SelectStep { pop: pb::select_step::Pop::Last as i32, select_keys }
+    }
+
+    // a traverser with vertex 1 tagged as "a"(0), and vertex 2 tagged as "b"(1)
+    fn labeled_traverser() -> Traverser {
+        let v1 = Vertex::new(1, Some(Label::Id(0)), DefaultDetails::new(1, Label::Id(0)));
+        let v2 = Vertex::new(2, Some(Label::Id(0)), DefaultDetails::new(2, Label::Id(0)));
+        let mut tag_a = BitSet::new();
+        tag_a.insert(0);
+        let mut tag_b = BitSet::new();
+        tag_b.insert(1);
+        let mut traverser = Traverser::with_path(v1, &tag_a, Requirement::LABELED_PATH);
+        traverser.split(v2, &tag_b);
+        traverser
+    }
+
+    fn selected_entries(traverser: &Traverser) -> Vec<(Tag, ID)> {
+        let result = match traverser.get_object() {
+            Some(Object::DynOwned(obj)) => obj.try_downcast_ref::<ResultProperty>().unwrap(),
+            _ => panic!("select step should output a ResultProperty"),
+        };
+        result
+            .tag_entries
+            .iter()
+            .map(|(tag, value)| (*tag, value.graph_element.as_ref().unwrap().id()))
+            .collect()
+    }
+
+    #[test]
+    fn select_tags_in_order() {
+        let select_ab = select_step_pb(vec![0, 1]).gen_map().unwrap();
+        let result = select_ab.exec(labeled_traverser()).unwrap();
+        assert_eq!(selected_entries(&result), vec![(0, 1), (1, 2)]);
+
+        let select_ba = select_step_pb(vec![1, 0]).gen_map().unwrap();
+        let result = select_ba.exec(labeled_traverser()).unwrap();
+        assert_eq!(selected_entries(&result), vec![(1, 2), (0, 1)]);
+    }
+
+    #[test]
+    fn select_missing_tag() {
+        let select_ac = select_step_pb(vec![0, 2]).gen_map().unwrap();
+        assert!(select_ac.exec(labeled_traverser()).is_err());
+    }
+}
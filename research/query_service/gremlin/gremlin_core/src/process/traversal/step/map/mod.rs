@@ -72,6 +72,7 @@ impl MapFuncGen for pb::GremlinStep {
                     let requirements = Requirement::from_pb(requirements_pb)?;
                     Ok(Box::new(TransformTraverserStep { requirement: requirements, remove_tags }))
                 }
+                pb::gremlin_step::Step::SackStep(sack_step) => sack_step.gen_map(),
                 _ => Err(str_to_dyn_error("pb GremlinStep is not a Map Step")),
             }
         } else {
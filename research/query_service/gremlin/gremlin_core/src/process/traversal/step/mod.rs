@@ -70,8 +70,11 @@ mod util;
 use crate::structure::{Tag, INIT_TAG_NUM};
 use crate::FromPb;
 use bit_set::BitSet;
-pub use filter::FilterFuncGen;
-pub use flat_map::FlatMapFuncGen;
+pub use filter::{DedupStep, FilterFuncGen};
+pub use flat_map::{
+    fuse_flat_maps, install_flat_map, FlatMapFuncGen, FusedFlatMap, FusedIter, PropertyElement,
+    PropertyElementsStep, TraverserFlatMap,
+};
 pub use fold::{AccumFactoryGen, TraverserAccumulator};
 pub use group_by::KeyFunctionGen;
 pub use map::MapFuncGen;
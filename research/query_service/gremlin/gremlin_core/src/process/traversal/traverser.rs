@@ -17,9 +17,10 @@ use crate::generated::gremlin as pb;
 use crate::generated::gremlin::TraverserRequirement;
 use crate::process::traversal::path::{Path, PathItem, ResultPath};
 use crate::process::traversal::pop::Pop;
+use crate::process::traversal::sack::SackValue;
 use crate::structure::codec::ParseError;
 use crate::structure::{GraphElement, Tag};
-use crate::{DynIter, Element, FromPb};
+use crate::{DynIter, DynResult, Element, FromPb};
 use bit_set::BitSet;
 use dyn_type::Object;
 
@@ -71,16 +72,41 @@ impl FromPb<Vec<pb::TraverserRequirement>> for Requirement {
 }
 
 #[derive(Clone, Debug)]
-pub enum Traverser {
+pub enum TraverserValue {
     Path(Path),
     LabeledPath(Path),
     NoPath(GraphElement),
     Object(Object),
 }
 
+#[derive(Clone, Debug)]
+pub struct Traverser {
+    value: TraverserValue,
+    /// The number of identical traversers this traverser stands for, which is more than 1 only if
+    /// they are merged by `merge_bulk()` when `Requirement::BULK` is set. It is kept along with the
+    /// traverser through the map and flat_map steps.
+    pub bulk: u64,
+    /// The sack of the traverser, which is set only if `Requirement::SACK` is set, and is cloned
+    /// along with the traverser, e.g., by the flat_map steps.
+    pub sack: Option<SackValue>,
+    /// The loop counters of the nested `repeat()`s the traverser is in, with the innermost loop on
+    /// top, which are maintained by `LoopStep`.
+    pub loops: Vec<u32>,
+}
+
+impl From<TraverserValue> for Traverser {
+    fn from(value: TraverserValue) -> Self {
+        Traverser { value, bulk: 1, sack: None, loops: vec![] }
+    }
+}
+
 impl Traverser {
     pub fn new<E: Into<GraphElement>>(e: E) -> Self {
-        Traverser::NoPath(e.into())
+        TraverserValue::NoPath(e.into()).into()
+    }
+
+    pub fn object<T: Into<Object>>(o: T) -> Self {
+        TraverserValue::Object(o.into()).into()
     }
 
     pub fn with_path<E: Into<GraphElement>>(e: E, tags: &BitSet, requirement: Requirement) -> Self {
@@ -88,46 +114,58 @@ impl Traverser {
             debug!("start a path traverser");
             let mut path = Path::new(e.into(), false);
             path.extend(tags);
-            Traverser::Path(path)
+            TraverserValue::Path(path).into()
         } else {
             debug!("start a label path traverser");
             let mut path = Path::new(e.into(), true);
             path.extend(tags);
-            Traverser::LabeledPath(path)
+            TraverserValue::LabeledPath(path).into()
         }
     }
 
+    /// The counter of the innermost loop, e.g., `loops()` in `repeat(out()).until(loops().is(3))`,
+    /// which is 0 if not in a loop.
+    pub fn get_loops(&self) -> u32 {
+        self.loops.last().copied().unwrap_or(0)
+    }
+
     pub fn get_element(&self) -> Option<&GraphElement> {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => p.head().and_then(|x| x.as_element()),
-            Traverser::NoPath(e) => Some(e),
-            Traverser::Object(_) => None,
+        match &self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => {
+                p.head().and_then(|x| x.as_element())
+            }
+            TraverserValue::NoPath(e) => Some(e),
+            TraverserValue::Object(_) => None,
         }
     }
 
     pub fn get_element_mut(&mut self) -> Option<&mut GraphElement> {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => p.head_mut().as_mut_element(),
-            Traverser::NoPath(e) => Some(e),
-            Traverser::Object(_) => None,
+        match &mut self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => {
+                p.head_mut().as_mut_element()
+            }
+            TraverserValue::NoPath(e) => Some(e),
+            TraverserValue::Object(_) => None,
         }
     }
 
     pub fn get_object(&self) -> Option<&Object> {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => {
+        match &self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => {
                 p.head().and_then(|x| x.as_detached())
             }
-            Traverser::NoPath(_) => None,
-            Traverser::Object(o) => Some(o),
+            TraverserValue::NoPath(_) => None,
+            TraverserValue::Object(o) => Some(o),
         }
     }
 
     pub fn get_object_mut(&mut self) -> Option<&mut Object> {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => p.head_mut().as_mut_detached(),
-            Traverser::NoPath(_) => None,
-            Traverser::Object(o) => Some(o),
+        match &mut self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => {
+                p.head_mut().as_mut_detached()
+            }
+            TraverserValue::NoPath(_) => None,
+            TraverserValue::Object(o) => Some(o),
         }
     }
 
@@ -140,66 +178,66 @@ impl Traverser {
     }
 
     pub fn split<E: Into<GraphElement>>(&mut self, e: E, tags: &BitSet) {
-        match self {
-            Traverser::Path(p) => {
+        match &mut self.value {
+            TraverserValue::Path(p) => {
                 p.extend_with(e.into(), tags, false);
             }
-            Traverser::LabeledPath(p) => {
+            TraverserValue::LabeledPath(p) => {
                 p.extend_with(e.into(), tags, true);
             }
-            Traverser::NoPath(ori) => *ori = e.into(),
-            Traverser::Object(_) => unimplemented!(),
+            TraverserValue::NoPath(ori) => *ori = e.into(),
+            TraverserValue::Object(_) => unimplemented!(),
         }
     }
 
     pub fn split_with_value<T: Into<Object>>(&mut self, o: T, tags: &BitSet) {
-        match self {
-            Traverser::Path(p) => {
+        match &mut self.value {
+            TraverserValue::Path(p) => {
                 p.extend_with(o.into(), tags, false);
             }
-            Traverser::LabeledPath(p) => {
+            TraverserValue::LabeledPath(p) => {
                 p.extend_with(o.into(), tags, true);
             }
-            Traverser::NoPath(_) => *self = Traverser::Object(o.into()),
-            Traverser::Object(ori) => {
+            TraverserValue::NoPath(_) => self.value = TraverserValue::Object(o.into()),
+            TraverserValue::Object(ori) => {
                 *ori = o.into();
             }
         }
     }
 
     pub fn remove_tags(&mut self, tags: &BitSet) {
-        match self {
-            Traverser::Path(p) => {
+        match &mut self.value {
+            TraverserValue::Path(p) => {
                 debug!("Remove tags {:?} in Path {:?}, but why?", tags, p);
                 p.remove_tag(tags)
             }
-            Traverser::LabeledPath(p) => p.remove_tag(tags),
-            Traverser::NoPath(e) => {
+            TraverserValue::LabeledPath(p) => p.remove_tag(tags),
+            TraverserValue::NoPath(e) => {
                 debug!("Try remove tags {:?} in NoPath {:?}, but will not", tags, e)
             }
-            Traverser::Object(o) => {
+            TraverserValue::Object(o) => {
                 debug!("Try remove tags {:?} in Unknown {:?}, but will not", tags, o)
             }
         }
     }
 
     pub fn add_tags(&mut self, tags: &BitSet) {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => p.extend(tags),
+        match &mut self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => p.extend(tags),
             _ => (),
         }
     }
 
     pub fn is_simple(&self) -> bool {
-        match self {
-            Traverser::Path(p) => p.is_simple(),
+        match &self.value {
+            TraverserValue::Path(p) => p.is_simple(),
             _ => true,
         }
     }
 
     pub fn select(&self, tag: &Tag) -> Option<&PathItem> {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => p.select(tag),
+        match &self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => p.select(tag),
             _ => None,
         }
     }
@@ -217,8 +255,8 @@ impl Traverser {
     }
 
     pub fn select_pop(&self, pop: Pop, tag: &Tag) -> Option<&PathItem> {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => match pop {
+        match &self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => match pop {
                 _ => p.select(tag),
             },
             _ => None,
@@ -244,24 +282,24 @@ impl Traverser {
     }
 
     pub fn has_cyclic_path(&self) -> bool {
-        match self {
-            Traverser::Path(p) => !p.is_simple(),
+        match &self.value {
+            TraverserValue::Path(p) => !p.is_simple(),
             _ => false,
         }
     }
 
     pub fn take_path(self) -> ResultPath {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => p.finalize(),
-            Traverser::NoPath(e) => ResultPath::new(vec![PathItem::OnGraph(e)]),
-            Traverser::Object(e) => ResultPath::new(vec![PathItem::Detached(e)]),
+        match self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => p.finalize(),
+            TraverserValue::NoPath(e) => ResultPath::new(vec![PathItem::OnGraph(e)]),
+            TraverserValue::Object(e) => ResultPath::new(vec![PathItem::Detached(e)]),
         }
     }
 
     pub fn get_path_len(&self) -> usize {
-        match self {
-            Traverser::Path(p) => p.length(),
-            Traverser::LabeledPath(p) => {
+        match &self.value {
+            TraverserValue::Path(p) => p.length(),
+            TraverserValue::LabeledPath(p) => {
                 debug!("May not be right, since this is label path length rather than path");
                 p.length()
             }
@@ -269,98 +307,168 @@ impl Traverser {
         }
     }
 
+    /// Whether the traverser can be merged with the identical ones by `merge_bulk()`, which is not
+    /// the case of the traversers with path, as `Traverser` compares on the head of the path only.
+    pub fn is_bulkable(&self) -> bool {
+        match self.value {
+            TraverserValue::NoPath(_) | TraverserValue::Object(_) => true,
+            TraverserValue::Path(_) | TraverserValue::LabeledPath(_) => false,
+        }
+    }
+
     pub fn transform(self, requirement: Requirement) -> Traverser {
-        match self {
-            Traverser::Path(p) => {
+        let value = match self.value {
+            TraverserValue::Path(p) => {
                 if requirement.contains(Requirement::PATH) {
-                    Traverser::Path(p)
+                    TraverserValue::Path(p)
                 } else if requirement.contains(Requirement::LABELED_PATH) {
-                    Traverser::LabeledPath(p)
+                    TraverserValue::LabeledPath(p)
                 } else {
                     // Assume it's object for now
                     match p.head() {
-                        Some(PathItem::OnGraph(e)) => Traverser::NoPath(e.clone()),
-                        Some(PathItem::Detached(o)) => Traverser::Object(o.clone()),
+                        Some(PathItem::OnGraph(e)) => TraverserValue::NoPath(e.clone()),
+                        Some(PathItem::Detached(o)) => TraverserValue::Object(o.clone()),
                         Some(PathItem::Empty) => unreachable!(),
                         None => unreachable!(),
                     }
                 }
             }
-            Traverser::LabeledPath(p) => {
+            TraverserValue::LabeledPath(p) => {
                 if requirement.contains(Requirement::PATH) {
                     debug!("Current is LabeledPath traverser, transform to Path should not happen");
-                    Traverser::Path(p)
+                    TraverserValue::Path(p)
                 } else if requirement.contains(Requirement::LABELED_PATH) {
-                    Traverser::LabeledPath(p)
+                    TraverserValue::LabeledPath(p)
                 } else {
                     match p.head() {
-                        Some(PathItem::OnGraph(e)) => Traverser::NoPath(e.clone()),
-                        Some(PathItem::Detached(o)) => Traverser::Object(o.clone()),
+                        Some(PathItem::OnGraph(e)) => TraverserValue::NoPath(e.clone()),
+                        Some(PathItem::Detached(o)) => TraverserValue::Object(o.clone()),
                         Some(PathItem::Empty) => unreachable!(),
                         None => unreachable!(),
                     }
                 }
             }
-            Traverser::NoPath(e) => {
+            TraverserValue::NoPath(e) => {
                 debug!("Current is NoPath traverser, transform will do nothing");
-                Traverser::NoPath(e)
+                TraverserValue::NoPath(e)
             }
-            Traverser::Object(o) => {
+            TraverserValue::Object(o) => {
                 debug!(
                     "Current is object traverser, transform will do nothing. It may happen when object is ResultPath"
                 );
-                Traverser::Object(o)
+                TraverserValue::Object(o)
             }
-        }
+        };
+        Traverser { value, ..self }
     }
 }
 
-impl Encode for Traverser {
-    fn write_to<W: WriteExt>(&self, writer: &mut W) -> std::io::Result<()> {
+/// The flags over the kind byte of an encoded `Traverser`, telling which of the bulk, sack and
+/// loops are written after its value, as they are left out while not set.
+const BULK_FLAG: u8 = 0x10;
+const SACK_FLAG: u8 = 0x20;
+const LOOPS_FLAG: u8 = 0x40;
+const KIND_MASK: u8 = 0x0f;
+
+impl TraverserValue {
+    fn write_with_flags<W: WriteExt>(&self, flags: u8, writer: &mut W) -> std::io::Result<()> {
         match self {
-            Traverser::Path(p) => {
-                writer.write_u8(0)?;
+            TraverserValue::Path(p) => {
+                writer.write_u8(flags)?;
                 p.write_to(writer)?;
             }
-            Traverser::NoPath(element) => {
-                writer.write_u8(1)?;
+            TraverserValue::NoPath(element) => {
+                writer.write_u8(1 | flags)?;
                 element.write_to(writer)?;
             }
-            Traverser::Object(object) => {
-                writer.write_u8(2)?;
+            TraverserValue::Object(object) => {
+                writer.write_u8(2 | flags)?;
                 object.write_to(writer)?;
             }
-            Traverser::LabeledPath(p) => {
-                writer.write_u8(3)?;
+            TraverserValue::LabeledPath(p) => {
+                writer.write_u8(3 | flags)?;
                 p.write_to(writer)?;
             }
         }
         Ok(())
     }
-}
 
-impl Decode for Traverser {
-    fn read_from<R: ReadExt>(reader: &mut R) -> std::io::Result<Self> {
+    fn read_with_flags<R: ReadExt>(reader: &mut R) -> std::io::Result<(Self, u8)> {
         let e = reader.read_u8()?;
-        match e {
+        let value = match e & KIND_MASK {
             0 => {
                 let p = <Path>::read_from(reader)?;
-                Ok(Traverser::Path(p))
+                TraverserValue::Path(p)
             }
             1 => {
                 let element = <GraphElement>::read_from(reader)?;
-                Ok(Traverser::NoPath(element))
+                TraverserValue::NoPath(element)
             }
             2 => {
                 let object = <Object>::read_from(reader)?;
-                Ok(Traverser::Object(object))
+                TraverserValue::Object(object)
             }
             3 => {
                 let p = <Path>::read_from(reader)?;
-                Ok(Traverser::LabeledPath(p))
+                TraverserValue::LabeledPath(p)
             }
-            _ => Err(io::Error::new(io::ErrorKind::Other, "unreachable")),
+            _ => return Err(io::Error::new(io::ErrorKind::Other, "unreachable")),
+        };
+        Ok((value, e & !KIND_MASK))
+    }
+}
+
+impl Encode for TraverserValue {
+    fn write_to<W: WriteExt>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_with_flags(0, writer)
+    }
+}
+
+impl Decode for TraverserValue {
+    fn read_from<R: ReadExt>(reader: &mut R) -> std::io::Result<Self> {
+        let (value, flags) = TraverserValue::read_with_flags(reader)?;
+        if flags != 0 {
+            Err(io::Error::new(io::ErrorKind::Other, "unreachable"))
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+impl Encode for Traverser {
+    fn write_to<W: WriteExt>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut flags = 0;
+        if self.bulk != 1 {
+            flags |= BULK_FLAG;
+        }
+        if self.sack.is_some() {
+            flags |= SACK_FLAG;
+        }
+        if !self.loops.is_empty() {
+            flags |= LOOPS_FLAG;
+        }
+        self.value.write_with_flags(flags, writer)?;
+        if flags & BULK_FLAG != 0 {
+            writer.write_u64(self.bulk)?;
+        }
+        if let Some(sack) = self.sack.as_ref() {
+            sack.write_to(writer)?;
+        }
+        if flags & LOOPS_FLAG != 0 {
+            self.loops.write_to(writer)?;
         }
+        Ok(())
+    }
+}
+
+impl Decode for Traverser {
+    fn read_from<R: ReadExt>(reader: &mut R) -> std::io::Result<Self> {
+        let (value, flags) = TraverserValue::read_with_flags(reader)?;
+        let bulk = if flags & BULK_FLAG != 0 { reader.read_u64()? } else { 1 };
+        let sack =
+            if flags & SACK_FLAG != 0 { Some(<SackValue>::read_from(reader)?) } else { None };
+        let loops = if flags & LOOPS_FLAG != 0 { <Vec<u32>>::read_from(reader)? } else { vec![] };
+        Ok(Traverser { value, bulk, sack, loops })
     }
 }
 
@@ -396,26 +504,28 @@ impl PartialEq for Traverser {
             }
         };
 
-        match (self, other) {
+        match (&self.value, &other.value) {
             // Path compare with Path
-            (Traverser::Path(p1), Traverser::Path(p2))
-            | (Traverser::Path(p1), Traverser::LabeledPath(p2))
-            | (Traverser::LabeledPath(p1), Traverser::Path(p2))
-            | (Traverser::LabeledPath(p1), Traverser::LabeledPath(p2)) => p1.is_head_eq(p2),
+            (TraverserValue::Path(p1), TraverserValue::Path(p2))
+            | (TraverserValue::Path(p1), TraverserValue::LabeledPath(p2))
+            | (TraverserValue::LabeledPath(p1), TraverserValue::Path(p2))
+            | (TraverserValue::LabeledPath(p1), TraverserValue::LabeledPath(p2)) => {
+                p1.is_head_eq(p2)
+            }
             // Path compare with NoPath, namely GraphElement
-            (Traverser::Path(p), Traverser::NoPath(e))
-            | (Traverser::LabeledPath(p), Traverser::NoPath(e))
-            | (Traverser::NoPath(e), Traverser::Path(p))
-            | (Traverser::NoPath(e), Traverser::LabeledPath(p)) => _is_path_eq_elem(p, e),
+            (TraverserValue::Path(p), TraverserValue::NoPath(e))
+            | (TraverserValue::LabeledPath(p), TraverserValue::NoPath(e))
+            | (TraverserValue::NoPath(e), TraverserValue::Path(p))
+            | (TraverserValue::NoPath(e), TraverserValue::LabeledPath(p)) => _is_path_eq_elem(p, e),
             // Path compare with Object
-            (Traverser::Path(p), Traverser::Object(o))
-            | (Traverser::LabeledPath(p), Traverser::Object(o))
-            | (Traverser::Object(o), Traverser::Path(p))
-            | (Traverser::Object(o), Traverser::LabeledPath(p)) => _is_path_eq_obj(p, o),
+            (TraverserValue::Path(p), TraverserValue::Object(o))
+            | (TraverserValue::LabeledPath(p), TraverserValue::Object(o))
+            | (TraverserValue::Object(o), TraverserValue::Path(p))
+            | (TraverserValue::Object(o), TraverserValue::LabeledPath(p)) => _is_path_eq_obj(p, o),
             // GraphElement compare with GraphElement
-            (Traverser::NoPath(e1), Traverser::NoPath(e2)) => e1 == e2,
+            (TraverserValue::NoPath(e1), TraverserValue::NoPath(e2)) => e1 == e2,
             // Object compare with Object
-            (Traverser::Object(o1), Traverser::Object(o2)) => o1 == o2,
+            (TraverserValue::Object(o1), TraverserValue::Object(o2)) => o1 == o2,
             // `false` for all other cases
             (_, _) => false,
         }
@@ -471,34 +581,34 @@ impl<T: Send> ShadeSync<T> {
 pub struct TraverserSplitIter<E> {
     tags: Arc<BitSet>,
     origin: Traverser,
-    children: DynIter<E>,
+    children: DynIter<DynResult<E>>,
 }
 
 impl<E> TraverserSplitIter<E> {
-    pub fn new(origin: Traverser, tags: &Arc<BitSet>, children: DynIter<E>) -> Self {
+    pub fn new(origin: Traverser, tags: &Arc<BitSet>, children: DynIter<DynResult<E>>) -> Self {
         TraverserSplitIter { tags: tags.clone(), origin, children }
     }
 }
 
 impl<E: Into<GraphElement>> Iterator for TraverserSplitIter<E> {
-    type Item = Traverser;
+    type Item = DynResult<Traverser>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut traverser = self.origin.clone();
-        match self.children.next() {
-            Some(elem) => {
+        match self.children.next()? {
+            Ok(elem) => {
                 traverser.split(elem, &self.tags);
-                Some(traverser)
+                Some(Ok(traverser))
             }
-            None => None,
+            Err(e) => Some(Err(e)),
         }
     }
 }
 
 impl Hash for Traverser {
     fn hash<H: Hasher>(&self, mut state: &mut H) {
-        match self {
-            Traverser::Path(p) | Traverser::LabeledPath(p) => {
+        match &self.value {
+            TraverserValue::Path(p) | TraverserValue::LabeledPath(p) => {
                 let head = p.head();
                 match head {
                     Some(PathItem::OnGraph(e)) => e.id().hash(&mut state),
@@ -509,8 +619,8 @@ impl Hash for Traverser {
                     None => "~NONE".hash(&mut state),
                 }
             }
-            Traverser::NoPath(e) => e.id().hash(&mut state),
-            Traverser::Object(o) => o.hash(&mut state),
+            TraverserValue::NoPath(e) => e.id().hash(&mut state),
+            TraverserValue::Object(o) => o.hash(&mut state),
         }
     }
 }
@@ -523,6 +633,6 @@ unsafe impl Sync for Traverser {}
 impl Traverser {
     pub fn with<T: Data + Eq>(raw: T) -> Self {
         let v = ShadeSync { inner: raw };
-        Traverser::Object(Object::DynOwned(Box::new(v)))
+        Traverser::object(Object::DynOwned(Box::new(v)))
     }
 }
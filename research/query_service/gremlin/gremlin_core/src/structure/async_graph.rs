@@ -0,0 +1,256 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use crate::structure::{
+    try_statement, Direction, Edge, GraphProxy, QueryParams, Statement, Vertex, ID,
+};
+use crate::{DynIter, DynResult};
+use futures_util::stream::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+pub type DynStream<T> = Pin<Box<dyn Stream<Item = T> + Send>>;
+
+/// The asynchronous variant of `GraphProxy`, e.g., for the remote storages, which returns streams
+/// instead of iterators so as not to block on IO. It can be used as a `GraphProxy` via
+/// `BlockingGraphProxy`.
+pub trait AsyncGraphProxy: Send + Sync + 'static {
+    fn scan_vertex(&self, params: &QueryParams<Vertex>) -> DynResult<DynStream<Vertex>>;
+
+    fn scan_edge(&self, params: &QueryParams<Edge>) -> DynResult<DynStream<Edge>>;
+
+    fn get_vertex(&self, ids: &[ID], params: &QueryParams<Vertex>) -> DynResult<DynStream<Vertex>>;
+
+    fn get_edge(&self, ids: &[ID], params: &QueryParams<Edge>) -> DynResult<DynStream<Edge>>;
+
+    /// Explore the adjacent vertices of `src` along `direction`, where each item may fail,
+    /// e.g., a remote read fails mid-stream.
+    fn explore_vertex(
+        &self, src: ID, direction: Direction, params: &QueryParams<Vertex>,
+    ) -> DynResult<DynStream<DynResult<Vertex>>>;
+
+    /// Explore the adjacent edges of `src` along `direction`, the same as `explore_vertex()`.
+    fn explore_edge(
+        &self, src: ID, direction: Direction, params: &QueryParams<Edge>,
+    ) -> DynResult<DynStream<DynResult<Edge>>>;
+}
+
+/// Drive a stream on the runtime `handle`, blocking the current thread on each item.
+struct BlockingIter<T> {
+    stream: DynStream<T>,
+    handle: Handle,
+}
+
+impl<T> Iterator for BlockingIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let stream = &mut self.stream;
+        self.handle.block_on(stream.next())
+    }
+}
+
+/// Adapt an `AsyncGraphProxy` to `GraphProxy` by driving its streams on the runtime `handle`.
+/// Note that the methods must not be called within the asynchronous context of the runtime,
+/// which is the case of the pegasus workers.
+pub struct BlockingGraphProxy<A: AsyncGraphProxy> {
+    graph: Arc<A>,
+    handle: Handle,
+}
+
+impl<A: AsyncGraphProxy> BlockingGraphProxy<A> {
+    pub fn new(graph: A, handle: Handle) -> Self {
+        BlockingGraphProxy { graph: Arc::new(graph), handle }
+    }
+
+    fn blocking_iter<T: Send + 'static>(&self, stream: DynStream<T>) -> DynIter<T> {
+        Box::new(BlockingIter { stream, handle: self.handle.clone() })
+    }
+}
+
+impl<A: AsyncGraphProxy> GraphProxy for BlockingGraphProxy<A> {
+    fn scan_vertex(
+        &self, params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        Ok(self.blocking_iter(self.graph.scan_vertex(params)?))
+    }
+
+    fn scan_edge(
+        &self, params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        Ok(self.blocking_iter(self.graph.scan_edge(params)?))
+    }
+
+    fn get_vertex(
+        &self, ids: &[ID], params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        Ok(self.blocking_iter(self.graph.get_vertex(ids, params)?))
+    }
+
+    fn get_edge(
+        &self, ids: &[ID], params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        Ok(self.blocking_iter(self.graph.get_edge(ids, params)?))
+    }
+
+    fn prepare_explore_vertex(
+        &self, direction: Direction, params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Statement<ID, Vertex>>> {
+        let graph = self.graph.clone();
+        let handle = self.handle.clone();
+        let params = params.clone();
+        let stmt = move |src: ID| {
+            let stream = graph.explore_vertex(src, direction, &params)?;
+            Ok(Box::new(BlockingIter { stream, handle: handle.clone() })
+                as DynIter<DynResult<Vertex>>)
+        };
+        Ok(try_statement::<ID, Vertex>(Box::new(stmt)))
+    }
+
+    fn prepare_explore_edge(
+        &self, direction: Direction, params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Statement<ID, Edge>>> {
+        let graph = self.graph.clone();
+        let handle = self.handle.clone();
+        let params = params.clone();
+        let stmt = move |src: ID| {
+            let stream = graph.explore_edge(src, direction, &params)?;
+            Ok(Box::new(BlockingIter { stream, handle: handle.clone() })
+                as DynIter<DynResult<Edge>>)
+        };
+        Ok(try_statement::<ID, Edge>(Box::new(stmt)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structure::{DefaultDetails, DynDetails, Label};
+    use crate::Element;
+    use futures_util::stream;
+
+    /// A mock asynchronous graph of a path `0 -> 1 -> 2 -> 3`, which yields to the runtime
+    /// before each output. Exploring from the `broken` vertex fails after the first neighbor.
+    struct AsyncPathGraph {
+        len: ID,
+        broken: Option<ID>,
+    }
+
+    impl AsyncPathGraph {
+        fn lazy_stream<T: Send + 'static>(items: Vec<T>) -> DynStream<T> {
+            Box::pin(stream::iter(items).then(|item| async move {
+                tokio::task::yield_now().await;
+                item
+            }))
+        }
+
+        fn vertex(id: ID) -> Vertex {
+            Vertex::new(id, Some(Label::Id(0)), DefaultDetails::new(id, Label::Id(0)))
+        }
+
+        fn edge(src: ID) -> Edge {
+            let details = DynDetails::new(DefaultDetails::new(src, Label::Id(1)));
+            Edge::new(src, Some(Label::Id(1)), src, src + 1, details)
+        }
+
+        fn explore_stream<T: Send + 'static>(
+            &self, src: ID, items: Vec<T>,
+        ) -> DynStream<DynResult<T>> {
+            let mut items: Vec<DynResult<T>> = items.into_iter().map(Ok).collect();
+            if self.broken == Some(src) {
+                items.truncate(1);
+                items.push(Err(crate::str_to_dyn_error("remote read failure")));
+            }
+            Self::lazy_stream(items)
+        }
+    }
+
+    impl AsyncGraphProxy for AsyncPathGraph {
+        fn scan_vertex(&self, _: &QueryParams<Vertex>) -> DynResult<DynStream<Vertex>> {
+            Ok(Self::lazy_stream((0..self.len).map(Self::vertex).collect()))
+        }
+
+        fn scan_edge(&self, _: &QueryParams<Edge>) -> DynResult<DynStream<Edge>> {
+            Ok(Self::lazy_stream((0..self.len - 1).map(Self::edge).collect()))
+        }
+
+        fn get_vertex(&self, ids: &[ID], _: &QueryParams<Vertex>) -> DynResult<DynStream<Vertex>> {
+            let ids = ids.iter().filter(|id| **id < self.len);
+            Ok(Self::lazy_stream(ids.map(|id| Self::vertex(*id)).collect()))
+        }
+
+        fn get_edge(&self, ids: &[ID], _: &QueryParams<Edge>) -> DynResult<DynStream<Edge>> {
+            let ids = ids.iter().filter(|id| **id + 1 < self.len);
+            Ok(Self::lazy_stream(ids.map(|id| Self::edge(*id)).collect()))
+        }
+
+        fn explore_vertex(
+            &self, src: ID, direction: Direction, _: &QueryParams<Vertex>,
+        ) -> DynResult<DynStream<DynResult<Vertex>>> {
+            let mut neighbors = vec![];
+            if direction != Direction::In && src + 1 < self.len {
+                neighbors.push(Self::vertex(src + 1));
+            }
+            if direction != Direction::Out && src > 0 && src < self.len {
+                neighbors.push(Self::vertex(src - 1));
+            }
+            Ok(self.explore_stream(src, neighbors))
+        }
+
+        fn explore_edge(
+            &self, src: ID, direction: Direction, _: &QueryParams<Edge>,
+        ) -> DynResult<DynStream<DynResult<Edge>>> {
+            let mut edges = vec![];
+            if direction != Direction::In && src + 1 < self.len {
+                edges.push(Self::edge(src));
+            }
+            if direction != Direction::Out && src > 0 && src < self.len {
+                edges.push(Self::edge(src - 1));
+            }
+            Ok(self.explore_stream(src, edges))
+        }
+    }
+
+    #[test]
+    fn blocking_graph_proxy_test() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let graph = AsyncPathGraph { len: 4, broken: Some(2) };
+        let graph = BlockingGraphProxy::new(graph, runtime.handle().clone());
+
+        let params = QueryParams::default();
+        let ids = graph.scan_vertex(&params).unwrap().map(|v| v.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        let ids = graph.get_vertex(&[2, 5], &params).unwrap().map(|v| v.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![2]);
+
+        let params = QueryParams::default();
+        let edges =
+            graph.scan_edge(&params).unwrap().map(|e| (e.src_id, e.dst_id)).collect::<Vec<_>>();
+        assert_eq!(edges, vec![(0, 1), (1, 2), (2, 3)]);
+
+        let stmt = graph.prepare_explore_vertex(Direction::Both, &QueryParams::default()).unwrap();
+        let ids = stmt.exec(1).unwrap().map(|v| v.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 0]);
+        let stmt = graph.prepare_explore_edge(Direction::In, &QueryParams::default()).unwrap();
+        let edges = stmt.exec(3).unwrap().map(|e| (e.src_id, e.dst_id)).collect::<Vec<_>>();
+        assert_eq!(edges, vec![(2, 3)]);
+
+        // the remote read failing mid-stream is surfaced by the input exploring it
+        let stmt = graph.prepare_explore_vertex(Direction::Both, &QueryParams::default()).unwrap();
+        assert!(stmt.try_exec(2).unwrap().collect::<DynResult<Vec<_>>>().is_err());
+        assert_eq!(stmt.try_exec(0).unwrap().count(), 1);
+    }
+}
@@ -13,10 +13,17 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use crate::structure::element::{read_id, write_id, Element, Label, ID};
-use crate::structure::property::DynDetails;
+use crate::generated::common as common_pb;
+use crate::structure::codec::ParseError;
+use crate::structure::element::{id_from_pb, id_to_pb, read_id, write_id, Element, Label, ID};
+use crate::structure::property::{
+    properties_from_pb, properties_to_pb, DefaultDetails, DynDetails, FromProperty, PropKey,
+};
 use crate::structure::Details;
+use crate::FromPb;
+use dyn_type::CastError;
 use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
+use std::hash::{Hash, Hasher};
 use std::io;
 
 #[derive(Clone)]
@@ -68,6 +75,63 @@ impl Edge {
     pub fn get_dst_label(&self)-> Option<&Label>  {
         self.dst_label.as_ref()
     }
+
+    /// Get the property of `key` cast into `T`, e.g., `get_property_as::<i64>(&"age".into())`.
+    pub fn get_property_as<T: FromProperty>(&self, key: &PropKey) -> Result<Option<T>, CastError> {
+        self.properties.get_property_as(key)
+    }
+
+    /// Encode the ids, labels and loaded properties into pb, e.g., to be shuffled across the network.
+    pub fn to_pb(&self) -> Result<common_pb::Edge, ParseError> {
+        Ok(common_pb::Edge {
+            id: id_to_pb(self.id),
+            label: Some(self.label().to_pb()),
+            src_id: id_to_pb(self.src_id),
+            dst_id: id_to_pb(self.dst_id),
+            src_label: self.src_label.as_ref().map(|l| l.to_pb()),
+            dst_label: self.dst_label.as_ref().map(|l| l.to_pb()),
+            properties: properties_to_pb(&self.properties)?,
+        })
+    }
+}
+
+impl FromPb<common_pb::Edge> for Edge {
+    fn from_pb(edge: common_pb::Edge) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        let id = id_from_pb(&edge.id)?;
+        let label =
+            edge.label.ok_or(ParseError::OtherErr("label is missing in edge".to_string()))?;
+        let label = Label::from_pb(label)?;
+        let properties = properties_from_pb(edge.properties)?;
+        let details = DefaultDetails::new_with_prop(id, label.clone(), properties);
+        Ok(Edge {
+            id,
+            src_id: id_from_pb(&edge.src_id)?,
+            dst_id: id_from_pb(&edge.dst_id)?,
+            label: Some(label),
+            src_label: edge.src_label.map(Label::from_pb).transpose()?,
+            dst_label: edge.dst_label.map(Label::from_pb).transpose()?,
+            properties: DynDetails::new(details),
+        })
+    }
+}
+
+/// Two `Edge`s are compared and hashed by their ids only, regardless of the properties they carry,
+/// e.g., two copies of the same edge with different properties loaded are equal.
+impl PartialEq for Edge {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Edge {}
+
+impl Hash for Edge {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
 }
 
 impl Encode for Edge {
@@ -13,7 +13,10 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use crate::generated::common as common_pb;
+use crate::structure::codec::ParseError;
 use crate::structure::property::DynDetails;
+use crate::FromPb;
 use dyn_type::object::Primitives;
 use dyn_type::Object;
 pub use edge::Edge;
@@ -38,6 +41,20 @@ pub fn read_id<R: ReadExt>(reader: &mut R) -> io::Result<ID> {
     reader.read_u128()
 }
 
+/// Encode the id into the big-endian bytes of pb.
+pub fn id_to_pb(id: ID) -> Vec<u8> {
+    id.to_be_bytes().to_vec()
+}
+
+pub fn id_from_pb(bytes: &[u8]) -> Result<ID, ParseError> {
+    let mut buf = [0u8; ID_BITS / 8];
+    if bytes.len() != buf.len() {
+        return Err(ParseError::OtherErr(format!("invalid id of {} bytes in pb", bytes.len())));
+    }
+    buf.copy_from_slice(bytes);
+    Ok(ID::from_be_bytes(buf))
+}
+
 #[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum Label {
     Str(String),
@@ -51,6 +68,27 @@ impl Label {
             Label::Id(id) => Object::Primitive(Primitives::Integer(*id as i32)),
         }
     }
+
+    pub fn to_pb(&self) -> common_pb::Label {
+        let item = match self {
+            Label::Str(s) => common_pb::label::Item::Name(s.clone()),
+            Label::Id(id) => common_pb::label::Item::NameId(*id as i32),
+        };
+        common_pb::Label { item: Some(item) }
+    }
+}
+
+impl FromPb<common_pb::Label> for Label {
+    fn from_pb(label: common_pb::Label) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        match label.item {
+            Some(common_pb::label::Item::Name(name)) => Ok(Label::Str(name)),
+            Some(common_pb::label::Item::NameId(name_id)) => Ok(Label::Id(name_id as LabelId)),
+            None => Err(ParseError::InvalidData),
+        }
+    }
 }
 
 impl Encode for Label {
@@ -13,10 +13,17 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
-use crate::structure::element::{read_id, write_id, Element, Label, ID};
-use crate::structure::property::DynDetails;
+use crate::generated::common as common_pb;
+use crate::structure::codec::ParseError;
+use crate::structure::element::{id_from_pb, id_to_pb, read_id, write_id, Element, Label, ID};
+use crate::structure::property::{
+    properties_from_pb, properties_to_pb, DefaultDetails, DynDetails, FromProperty, PropKey,
+};
 use crate::structure::Details;
+use crate::FromPb;
+use dyn_type::CastError;
 use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
+use std::hash::{Hash, Hasher};
 use std::io;
 
 #[derive(Clone)]
@@ -30,6 +37,35 @@ impl Vertex {
     pub fn new<D: Details + 'static>(id: ID, label: Option<Label>, details: D) -> Self {
         Vertex { id, label, details: DynDetails::new(details) }
     }
+
+    /// Get the property of `key` cast into `T`, e.g., `get_property_as::<i64>(&"age".into())`.
+    pub fn get_property_as<T: FromProperty>(&self, key: &PropKey) -> Result<Option<T>, CastError> {
+        self.details.get_property_as(key)
+    }
+
+    /// Encode the id, label and loaded properties into pb, e.g., to be shuffled across the network.
+    pub fn to_pb(&self) -> Result<common_pb::Vertex, ParseError> {
+        Ok(common_pb::Vertex {
+            id: id_to_pb(self.id),
+            label: Some(self.label().to_pb()),
+            properties: properties_to_pb(&self.details)?,
+        })
+    }
+}
+
+impl FromPb<common_pb::Vertex> for Vertex {
+    fn from_pb(vertex: common_pb::Vertex) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        let id = id_from_pb(&vertex.id)?;
+        let label =
+            vertex.label.ok_or(ParseError::OtherErr("label is missing in vertex".to_string()))?;
+        let label = Label::from_pb(label)?;
+        let properties = properties_from_pb(vertex.properties)?;
+        let details = DefaultDetails::new_with_prop(id, label.clone(), properties);
+        Ok(Vertex::new(id, Some(label), details))
+    }
 }
 
 impl Element for Vertex {
@@ -50,6 +86,22 @@ impl Element for Vertex {
     }
 }
 
+/// Two `Vertex`s are compared and hashed by their ids only, regardless of the properties they carry,
+/// e.g., two copies of the same vertex with different properties loaded are equal.
+impl PartialEq for Vertex {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Vertex {}
+
+impl Hash for Vertex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
 impl Encode for Vertex {
     fn write_to<W: WriteExt>(&self, writer: &mut W) -> io::Result<()> {
         write_id(self.id, writer)?;
@@ -20,6 +20,7 @@ use crate::structure::filter::{BiPredicate, Predicate};
 use crate::{Element, ID};
 use std::collections::HashSet;
 
+#[derive(Clone)]
 pub struct HasId {
     pub cmp: EqCmp,
     pub expect: ExpectValue<ID>,
@@ -48,6 +49,7 @@ impl<E: Element> Predicate<E> for HasId {
     }
 }
 
+#[derive(Clone)]
 pub struct ContainsId {
     pub cmp: Contains,
     pub expect: HashSet<ID>,
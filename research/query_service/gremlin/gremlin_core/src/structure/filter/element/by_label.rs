@@ -21,6 +21,7 @@ use crate::structure::filter::{BiPredicate, Predicate};
 use crate::structure::Element;
 use std::collections::HashSet;
 
+#[derive(Clone)]
 pub struct HasLabel {
     pub cmp: EqCmp,
     pub expect: ExpectValue<Label>,
@@ -48,6 +49,7 @@ impl Reverse for HasLabel {
     }
 }
 
+#[derive(Clone)]
 pub struct ContainsLabel {
     pub cmp: Contains,
     pub expect: HashSet<Label>,
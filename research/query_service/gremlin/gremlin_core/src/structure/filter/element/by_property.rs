@@ -21,6 +21,7 @@ use crate::structure::{ get_tlv_type, TlvType,with_tlv, BiPredicate, Details, Dy
 use dyn_type::Object;
 use std::collections::HashSet;
 
+#[derive(Clone)]
 pub struct HasProperty {
     pub key: PropKey,
     pub cmp: Compare,
@@ -76,6 +77,7 @@ impl Reverse for HasProperty {
     }
 }
 
+#[derive(Clone)]
 pub struct ContainsProperty {
     pub key: PropKey,
     pub cmp: Contains,
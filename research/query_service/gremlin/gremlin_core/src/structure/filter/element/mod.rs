@@ -14,6 +14,7 @@
 //! limitations under the License.
 
 use crate::structure::element::Label;
+use crate::structure::filter::compare::EqCmp;
 use crate::structure::filter::{BiPredicate, Predicate};
 use crate::{Element, ID};
 use std::cell::RefCell;
@@ -36,6 +37,7 @@ pub enum TlvType {
     LeftValue,
     RightValue,
 }
+#[derive(Clone)]
 pub enum ExpectValue<T: DynType> {
     Local(T),
     TLV,
@@ -138,6 +140,7 @@ impl Reverse for bool {
 }
 
 #[enum_dispatch(Reverse)]
+#[derive(Clone)]
 pub enum ElementFilter {
     PassBy(bool),
     HasId(HasId),
@@ -148,6 +151,19 @@ pub enum ElementFilter {
     ContainsProperty(ContainsProperty),
 }
 
+impl ElementFilter {
+    /// Get the label `X` if the filter is exactly `label == X` with `X` given locally.
+    pub fn as_label_eq(&self) -> Option<&Label> {
+        match self {
+            ElementFilter::HasLabel(HasLabel {
+                cmp: EqCmp::Eq,
+                expect: ExpectValue::Local(label),
+            }) => Some(label),
+            _ => None,
+        }
+    }
+}
+
 impl<E: Element> Predicate<E> for ElementFilter {
     fn test(&self, entry: &E) -> Option<bool> {
         match self {
@@ -15,6 +15,7 @@
 
 use crate::process::traversal::traverser::Traverser;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 #[enum_dispatch]
 pub trait Predicate<T> {
@@ -44,7 +45,7 @@ mod contains;
 mod element;
 mod traverser;
 
-use crate::structure::{GraphElement, Tag};
+use crate::structure::{Element, GraphElement, Label, Tag};
 pub use element::*;
 pub use traverser::*;
 
@@ -52,6 +53,20 @@ pub enum Filter<T, P: Predicate<T>> {
     Ph(PhantomData<T>),
     Simple(P),
     Chain(Chain<T, P>),
+    /// A filter shared with others, e.g., the existing filter of `QueryParams` extended by
+    /// `QueryParams::and_filter()`, which can be tested but not modified.
+    Shared(Arc<Filter<T, P>>),
+}
+
+impl<T, P: Predicate<T> + Clone> Clone for Filter<T, P> {
+    fn clone(&self) -> Self {
+        match self {
+            Filter::Ph(_) => Filter::Ph(PhantomData),
+            Filter::Simple(p) => Filter::Simple(p.clone()),
+            Filter::Chain(chain) => Filter::Chain(chain.clone()),
+            Filter::Shared(f) => Filter::Shared(f.clone()),
+        }
+    }
 }
 
 impl<T, P: Predicate<T>> Default for Filter<T, P> {
@@ -79,7 +94,7 @@ impl<T, P: Predicate<T>> Filter<T, P> {
             Filter::Ph(_) => {
                 let _ = std::mem::replace(self, f.into());
             }
-            Filter::Simple(_) => {
+            Filter::Simple(_) | Filter::Shared(_) => {
                 let old = std::mem::replace(self, Filter::Ph(PhantomData));
                 let mut upgrade = Filter::with_chain(old);
                 upgrade.and(f);
@@ -95,7 +110,7 @@ impl<T, P: Predicate<T>> Filter<T, P> {
             Filter::Ph(_) => {
                 let _ = std::mem::replace(self, f.into());
             }
-            Filter::Simple(_) => {
+            Filter::Simple(_) | Filter::Shared(_) => {
                 let old = std::mem::replace(self, Filter::Ph(PhantomData));
                 let mut upgrade = Filter::with_chain(old);
                 upgrade.or(f);
@@ -111,6 +126,7 @@ impl<T, P: Predicate<T>> Filter<T, P> {
             Filter::Ph(_) => Some(true),
             Filter::Simple(p) => p.test(entry),
             Filter::Chain(chain) => chain.test(entry),
+            Filter::Shared(f) => f.test(entry),
         }
     }
 
@@ -126,6 +142,7 @@ impl<T, P: Predicate<T>> Filter<T, P> {
                     n.filter.for_each(func);
                 }
             }
+            Filter::Shared(f) => f.for_each(func),
         }
     }
 
@@ -134,6 +151,19 @@ impl<T, P: Predicate<T>> Filter<T, P> {
             Filter::Ph(_) => true,
             Filter::Simple(_) => false,
             Filter::Chain(f) => f.is_empty(),
+            Filter::Shared(f) => f.is_empty(),
+        }
+    }
+}
+
+impl<T: Element> Filter<T, ElementFilter> {
+    /// Get the label `X` if the filter is a single label equality `label == X`, otherwise `None`.
+    pub fn as_label_eq(&self) -> Option<&Label> {
+        match self {
+            Filter::Simple(p) => p.as_label_eq(),
+            Filter::Chain(chain) if chain.list.len() == 1 => chain.list[0].filter.as_label_eq(),
+            Filter::Shared(f) => f.as_label_eq(),
+            _ => None,
         }
     }
 }
@@ -180,6 +210,12 @@ pub fn without_tag(filter: Filter<GraphElement, ElementFilter>) -> TraverserFilt
                 connect = node.next;
             }
         }
+        // the shared filters are only built by `QueryParams::and_filter()`, which are never tagged;
+        // other owners may still hold the `Arc`, in which case the inner filter is cloned.
+        Filter::Shared(shared) => {
+            let filter = Arc::try_unwrap(shared).unwrap_or_else(|shared| (*shared).clone());
+            return without_tag(filter);
+        }
     }
     tf
 }
@@ -218,6 +254,10 @@ pub fn with_tag(
                 connect = node.next;
             }
         }
+        Filter::Shared(shared) => {
+            let filter = Arc::try_unwrap(shared).unwrap_or_else(|shared| (*shared).clone());
+            return with_tag(tags, filter);
+        }
     }
     tf
 }
@@ -233,6 +273,12 @@ struct ChainNode<T, P: Predicate<T>> {
     next: ChainKind,
 }
 
+impl<T, P: Predicate<T> + Clone> Clone for ChainNode<T, P> {
+    fn clone(&self) -> Self {
+        ChainNode { filter: self.filter.clone(), next: self.next }
+    }
+}
+
 impl<T, P: Predicate<T>> ChainNode<T, P> {
     pub fn new(filter: Filter<T, P>) -> Self {
         ChainNode { filter, next: ChainKind::Or }
@@ -247,6 +293,12 @@ pub struct Chain<T, P: Predicate<T>> {
     list: Vec<ChainNode<T, P>>,
 }
 
+impl<T, P: Predicate<T> + Clone> Clone for Chain<T, P> {
+    fn clone(&self) -> Self {
+        Chain { list: self.list.clone() }
+    }
+}
+
 impl<T, P: Predicate<T>> Chain<T, P> {
     fn new<F: Into<Filter<T, P>>>(f: F) -> Self {
         let node = ChainNode::new(f.into());
@@ -286,7 +338,7 @@ impl<T, P: Predicate<T>> Chain<T, P> {
                 let node = ChainNode::new(next);
                 self.list.push(node);
             }
-            Filter::Simple(_) => {
+            Filter::Simple(_) | Filter::Shared(_) => {
                 let node = ChainNode::new(next);
                 self.list.push(node);
             }
@@ -395,4 +447,20 @@ mod test {
         assert_eq!(filter.test(&p3), Some(false));
         assert_eq!(filter.test(&p4), Some(false));
     }
+
+    #[test]
+    pub fn test_without_tag_on_shared_filter() {
+        use crate::structure::{DefaultDetails, Vertex};
+
+        let shared = Arc::new(Filter::with(has_id(Some(1))));
+        // another owner, e.g. the cached filter code of `QueryParams`, still holds the filter.
+        let other = shared.clone();
+        let filter = without_tag(Filter::Shared(shared));
+
+        let v1 = Vertex::new(1, Some(Label::Id(0)), DefaultDetails::new(1, Label::Id(0)));
+        let v2 = Vertex::new(2, Some(Label::Id(0)), DefaultDetails::new(2, Label::Id(0)));
+        assert_eq!(filter.test(&Traverser::new(v1)), Some(true));
+        assert_eq!(filter.test(&Traverser::new(v2)), Some(false));
+        assert_eq!(Arc::strong_count(&other), 1);
+    }
 }
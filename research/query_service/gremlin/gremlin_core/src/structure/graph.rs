@@ -17,13 +17,17 @@ use crate::generated::common as pb_common;
 use crate::generated::gremlin as pb;
 use crate::structure::codec::{pb_chain_to_filter, ParseError};
 use crate::structure::{
-    Direction, Edge, ElementFilter, Filter, Label, LabelId, PropKey, Vertex, ID,
+    Details, Direction, Edge, ElementFilter, Filter, Label, LabelId, Order, PropId, PropKey,
+    Vertex, ID,
 };
-use crate::{DynIter, DynResult, Element, FromPb};
+use crate::{str_to_dyn_error, DynIter, DynResult, Element, FromPb};
 use dyn_type::Object;
-use std::collections::HashMap;
+use graph_store::prelude::Schema;
+use prost::Message;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicPtr, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
 pub struct QueryParams<E: Element + Send + Sync> {
@@ -33,8 +37,18 @@ pub struct QueryParams<E: Element + Send + Sync> {
     pub filter: Option<Arc<Filter<E, ElementFilter>>>,
     pub partitions: Option<Vec<u64>>,
     pub extra_params: Option<HashMap<String, Object>>,
+    /// The order of the results, which is a hint for the storages that can return sorted results,
+    /// and can be ignored otherwise, as the results are always sorted in the runtime.
+    pub order_by: Vec<(PropKey, Order)>,
+    /// The encoded `pb::FilterChain`s that `filter` is conjoined from, which identify the filter by
+    /// content, e.g., for caching. It is kept along with the filter it is encoded for, so that
+    /// it is ignored once `filter` is replaced directly.
+    filter_code: Option<FilterCode<E>>,
 }
 
+/// A filter along with the encoded `pb::FilterChain`s it is conjoined from.
+type FilterCode<E> = (Arc<Filter<E, ElementFilter>>, Vec<Vec<u8>>);
+
 impl<E: Element + Send + Sync> Default for QueryParams<E> {
     fn default() -> Self {
         QueryParams {
@@ -44,6 +58,8 @@ impl<E: Element + Send + Sync> Default for QueryParams<E> {
             filter: None,
             partitions: None,
             extra_params: None,
+            order_by: vec![],
+            filter_code: None,
         }
     }
 }
@@ -59,12 +75,25 @@ impl<E: Element + Send + Sync> FromPb<Option<pb::QueryParams>> for QueryParams<E
                 .with_filter(query_params_pb.predicates)?
                 .with_limit(query_params_pb.limit)?
                 .with_required_properties(query_params_pb.required_properties)?
-                .with_extra_params(query_params_pb.extra_params)
+                .with_extra_params(query_params_pb.extra_params)?
+                .with_order_by(query_params_pb.order_by)
         })
     }
 }
 
 impl<E: Element + Send + Sync> QueryParams<E> {
+    /// Create the params querying for the elements of `labels` only, without building a
+    /// `pb::QueryParams`, e.g., in the tests or simple callers.
+    pub fn with_labels_only(labels: Vec<Label>) -> Self {
+        QueryParams { labels, ..Default::default() }
+    }
+
+    /// Create the params requiring the properties `props` only, where an empty `props` indicates
+    /// all properties, the same as in `with_required_properties()`.
+    pub fn with_props_only(props: Vec<PropKey>) -> Self {
+        QueryParams { props: Some(props), ..Default::default() }
+    }
+
     fn with_labels(
         mut self, labels_pb: Option<pb::query_params::Labels>,
     ) -> Result<Self, ParseError> {
@@ -77,7 +106,9 @@ impl<E: Element + Send + Sync> QueryParams<E> {
     fn with_filter(mut self, filter_chain_pb: Option<pb::FilterChain>) -> Result<Self, ParseError> {
         if let Some(ref filter_chain_pb) = filter_chain_pb {
             if let Some(filter) = pb_chain_to_filter(filter_chain_pb)? {
-                self.filter = Some(Arc::new(filter));
+                let filter = Arc::new(filter);
+                self.filter_code = Some((filter.clone(), vec![filter_chain_pb.encode_to_vec()]));
+                self.filter = Some(filter);
             }
         }
         Ok(self)
@@ -134,6 +165,135 @@ impl<E: Element + Send + Sync> QueryParams<E> {
         Ok(self)
     }
 
+    fn with_order_by(
+        mut self, order_by_pb: Vec<pb::query_params::OrderBy>,
+    ) -> Result<Self, ParseError> {
+        for order_by in order_by_pb {
+            let key = order_by
+                .key
+                .ok_or(ParseError::OtherErr("key is missing in order_by".to_string()))?;
+            let order = pb::order_by_compare_pair::Order::from_i32(order_by.order)
+                .ok_or(ParseError::InvalidData)?;
+            self.order_by.push((PropKey::from_pb(key)?, Order::from_pb(order)?));
+        }
+        Ok(self)
+    }
+
+    /// Get the label `X` if the filter is exactly `label == X`, which can be merged into `self.labels`
+    /// by the storages to query by label instead of testing the predicate on each element.
+    pub fn extract_label_eq(&self) -> Option<Label> {
+        self.filter.as_ref().and_then(|f| f.as_label_eq()).cloned()
+    }
+
+    /// Whether any property is required, i.e., `props` is `Some`, for all or some specific properties.
+    /// If not, the storages can skip decoding properties and return the structure only.
+    pub fn needs_properties(&self) -> bool {
+        self.props.is_some()
+    }
+
+    /// Whether there are any conditions that can be pushed down to the storages,
+    /// i.e., the labels, predicates, limit or order, instead of scanning everything.
+    pub fn is_queryable(&self) -> bool {
+        !self.labels.is_empty()
+            || self.filter.is_some()
+            || self.limit.is_some()
+            || !self.order_by.is_empty()
+    }
+
+    /// Conjoin the predicates of `filter_chain_pb` with the existing filter, if any, as
+    /// `existing && (filter_chain_pb)`. This is used to push predicates down into the query
+    /// incrementally. The existing filter is kept as is, as it may be shared with other `QueryParams`.
+    pub fn and_filter(&mut self, filter_chain_pb: &pb::FilterChain) -> Result<(), ParseError> {
+        if let Some(filter) = pb_chain_to_filter(filter_chain_pb)? {
+            let content = self.filter_content().map(|content| content.to_vec());
+            let combined = match self.filter.take() {
+                Some(existing) => {
+                    let mut combined = Filter::with_chain(Filter::Shared(existing));
+                    combined.and(filter);
+                    combined
+                }
+                None => filter,
+            };
+            let combined = Arc::new(combined);
+            self.filter_code = content.map(|mut content| {
+                content.push(filter_chain_pb.encode_to_vec());
+                (combined.clone(), content)
+            });
+            self.filter = Some(combined);
+        }
+        Ok(())
+    }
+
+    /// The content of `filter` as the encoded `pb::FilterChain`s it is conjoined from, which is
+    /// empty if there is no filter, or `None` if the filter is not built from `pb::FilterChain`s.
+    fn filter_content(&self) -> Option<&[Vec<u8>]> {
+        match (&self.filter, &self.filter_code) {
+            (None, _) => Some(&[]),
+            (Some(filter), Some((encoded, content))) if Arc::ptr_eq(filter, encoded) => {
+                Some(content.as_slice())
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve_with(&mut self, schema: &dyn Schema, is_vertex: bool) -> Result<(), ParseError> {
+        for label in self.labels.iter_mut() {
+            if let Label::Str(name) = label {
+                let label_id = if is_vertex {
+                    schema.get_vertex_label_id(name)
+                } else {
+                    schema.get_edge_label_id(name)
+                };
+                let label_id = label_id
+                    .ok_or(ParseError::OtherErr(format!("unknown label {:?} in schema", name)))?;
+                *label = Label::Id(label_id);
+            }
+        }
+        if let Some(props) = self.props.as_mut() {
+            // all labels in the schema are candidates if no label is specified
+            let label_ids: Vec<LabelId> = if self.labels.is_empty() {
+                if is_vertex {
+                    schema.get_vertex_label_ids()
+                } else {
+                    schema.get_edge_label_ids()
+                }
+            } else {
+                // the label names have been resolved into ids above
+                self.labels
+                    .iter()
+                    .filter_map(|l| if let Label::Id(id) = l { Some(*id) } else { None })
+                    .collect()
+            };
+            for prop in props.iter_mut() {
+                if let PropKey::Str(name) = prop {
+                    let mut prop_id = None;
+                    for label_id in label_ids.iter() {
+                        let label_schema = if is_vertex {
+                            schema.get_vertex_schema(*label_id)
+                        } else {
+                            schema.get_edge_schema(*label_id)
+                        };
+                        if let Some((_, index)) = label_schema.and_then(|s| s.get(name.as_str())) {
+                            if prop_id.is_some() && prop_id != Some(*index) {
+                                Err(ParseError::OtherErr(format!(
+                                    "property {:?} has different ids in the queried labels",
+                                    name
+                                )))?;
+                            }
+                            prop_id = Some(*index);
+                        }
+                    }
+                    let prop_id = prop_id.ok_or(ParseError::OtherErr(format!(
+                        "unknown property {:?} in schema",
+                        name
+                    )))?;
+                    *prop = PropKey::Id(prop_id as PropId);
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_extra_param(&self, key: &str) -> Option<&Object> {
         if let Some(ref extra_params) = self.extra_params {
             extra_params.get(key)
@@ -143,8 +303,37 @@ impl<E: Element + Send + Sync> QueryParams<E> {
     }
 }
 
-pub trait Statement<I, O>: Send + 'static {
+impl QueryParams<Vertex> {
+    /// Resolve the label and property names into their ids via the `schema`, for the storages
+    /// that can only query by ids. A property name is resolved if it has the same id in all
+    /// the queried labels, while a name unknown in the `schema` is an error.
+    pub fn resolve_props(&mut self, schema: &dyn Schema) -> Result<(), ParseError> {
+        self.resolve_with(schema, true)
+    }
+}
+
+impl QueryParams<Edge> {
+    /// Resolve the label and property names into their ids via the `schema`,
+    /// the same as `QueryParams<Vertex>::resolve_props()`.
+    pub fn resolve_props(&mut self, schema: &dyn Schema) -> Result<(), ParseError> {
+        self.resolve_with(schema, false)
+    }
+}
+
+/// A statement compiled by the storage, e.g., `prepare_explore_vertex()`, which is executed on each
+/// input. It is `Sync` so that a compiled statement can be shared by the workers without locking.
+pub trait Statement<I, O>: Send + Sync + 'static {
     fn exec(&self, next: I) -> DynResult<DynIter<O>>;
+
+    /// Execute the statement on `next` with the outputs that may fail during the iteration, e.g.,
+    /// a storage read fails mid-stream, where the error is returned in place of the output it fails
+    /// on. It is `exec()` with every output succeeded by default.
+    fn try_exec(&self, next: I) -> DynResult<TryIter<O>>
+    where
+        O: Send + 'static,
+    {
+        Ok(TryIter::new(Box::new(self.exec(next)?.map(Ok))))
+    }
 }
 
 impl<I, O, F: 'static> Statement<I, O> for F
@@ -156,6 +345,105 @@ where
     }
 }
 
+struct LimitStatement<I, O> {
+    stmt: Box<dyn Statement<I, O>>,
+    limit: usize,
+}
+
+impl<I: 'static, O: Send + 'static> Statement<I, O> for LimitStatement<I, O> {
+    fn exec(&self, next: I) -> DynResult<DynIter<O>> {
+        Ok(Box::new(self.stmt.exec(next)?.take(self.limit)))
+    }
+
+    fn try_exec(&self, next: I) -> DynResult<TryIter<O>> {
+        Ok(TryIter::new(Box::new(self.stmt.try_exec(next)?.take(self.limit))))
+    }
+}
+
+/// Truncate the outputs of `stmt` to `limit` for each input, e.g., `out().limit(5)` expands at most
+/// 5 neighbors of each vertex, in case that the statement of a storage does not apply the limit.
+pub fn limit_statement<I: 'static, O: Send + 'static>(
+    stmt: Box<dyn Statement<I, O>>, limit: Option<usize>,
+) -> Box<dyn Statement<I, O>> {
+    if let Some(limit) = limit {
+        Box::new(LimitStatement { stmt, limit })
+    } else {
+        stmt
+    }
+}
+
+/// A fallible iterator, which yields the outputs of `iter` lazily until it reaches the failing
+/// output, where the error is returned and the iteration stops, as the outputs after it are not
+/// trusted.
+pub struct TryIter<O> {
+    iter: Option<DynIter<DynResult<O>>>,
+}
+
+impl<O> TryIter<O> {
+    pub fn new(iter: DynIter<DynResult<O>>) -> Self {
+        TryIter { iter: Some(iter) }
+    }
+}
+
+impl<O> Iterator for TryIter<O> {
+    type Item = DynResult<O>;
+
+    fn next(&mut self) -> Option<DynResult<O>> {
+        let next = self.iter.as_mut()?.next();
+        if let Some(Err(_)) = next {
+            self.iter = None;
+        }
+        next
+    }
+}
+
+struct TryStatement<I, O> {
+    stmt: Box<dyn Statement<I, DynResult<O>>>,
+}
+
+impl<I: 'static, O: Send + 'static> Statement<I, O> for TryStatement<I, O> {
+    fn exec(&self, next: I) -> DynResult<DynIter<O>> {
+        // the error has no way out of the outputs, which stop at it instead
+        let outputs = self.try_exec(next)?.filter_map(|output| {
+            output.map_err(|e| error!("the outputs of the statement stop at: {}", e)).ok()
+        });
+        Ok(Box::new(outputs))
+    }
+
+    fn try_exec(&self, next: I) -> DynResult<TryIter<O>> {
+        Ok(TryIter::new(self.stmt.exec(next)?))
+    }
+}
+
+/// Adapt a statement whose outputs may fail during the iteration, e.g., a storage read fails
+/// mid-stream, to a `Statement`, whose `try_exec()` yields the outputs lazily and returns the error
+/// once it reaches the failing output. The `exec()` can only stop the outputs at the error.
+pub fn try_statement<I: 'static, O: Send + 'static>(
+    stmt: Box<dyn Statement<I, DynResult<O>>>,
+) -> Box<dyn Statement<I, O>> {
+    Box::new(TryStatement { stmt })
+}
+
+/// The optional capabilities of a storage, which are consulted to choose the plans instead of
+/// falling back to the default implementations or the computation in the runtime.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GraphCapability {
+    /// `index_scan_vertex` is backed by an index rather than a scan.
+    IndexScan,
+    /// `params.filter` is evaluated in the storage.
+    FilterPushdown,
+    /// `params.props` is respected, i.e., only the required properties are loaded.
+    PropertyPushdown,
+    /// `params.limit` is applied in the storage, for each input of the explore statements.
+    LimitPushdown,
+    /// The results are returned in the order of `params.order_by`.
+    OrderPushdown,
+    /// The results can be counted in the storage without being fetched, e.g., for `count()`.
+    CountPushdown,
+    /// The results can be fetched in pages.
+    Pagination,
+}
+
 pub trait GraphProxy: Send + Sync {
     fn scan_vertex(
         &self, params: &QueryParams<Vertex>,
@@ -180,6 +468,319 @@ pub trait GraphProxy: Send + Sync {
     fn prepare_explore_edge(
         &self, direction: Direction, params: &QueryParams<Edge>,
     ) -> DynResult<Box<dyn Statement<ID, Edge>>>;
+
+    /// Get the first outgoing edge from `src` to `dst` that satisfies `params`, e.g., the labels,
+    /// which is used to verify a single candidate edge without scanning all edges of `src`.
+    ///
+    /// The default implementation explores the outgoing edges of `src` via `prepare_explore_edge`,
+    /// where `params.limit` is ignored as it would apply before matching `dst`.
+    fn get_edge_between(
+        &self, src: ID, dst: ID, params: &QueryParams<Edge>,
+    ) -> DynResult<Option<Edge>> {
+        let mut params = params.clone();
+        params.limit = None;
+        let stmt = self.prepare_explore_edge(Direction::Out, &params)?;
+        for e in stmt.try_exec(src)? {
+            let e = e?;
+            if e.dst_id == dst {
+                return Ok(Some(e));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Scan the vertices of `label` whose property `prop` equals to `value`, which is meant to be
+    /// overridden by the backends with an index on `prop`.
+    ///
+    /// The default implementation is `scan_vertex_by_property()`.
+    fn index_scan_vertex(
+        &self, label: &Label, prop: &PropKey, value: &Object, params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        scan_vertex_by_property(self, label, prop, value, params)
+    }
+
+    /// Scan the edges for the undirected graph semantics, where each undirected edge is stored as two
+    /// directed half-edges, i.e., `src -> dst` and `dst -> src`, and only the one with `src < dst` is
+    /// returned as the representative. A self-loop is assumed to be stored once and always returned.
+    ///
+    /// The default implementation calls `scan_edge` and filters the half-edges,
+    /// where `params.limit` is applied after the filtering.
+    fn scan_edge_undirected(
+        &self, params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        let mut scan_params = params.clone();
+        scan_params.limit = None;
+        let edges = self.scan_edge(&scan_params)?.filter(|e| e.src_id <= e.dst_id);
+        if let Some(limit) = params.limit {
+            Ok(Box::new(edges.take(limit)))
+        } else {
+            Ok(Box::new(edges))
+        }
+    }
+
+    /// Get the vertices aligned with `ids`, i.e., the `i`-th result is the vertex of `ids[i]`,
+    /// or `None` if it is not found or filtered out by `params`. The duplicated ids are fetched
+    /// only once, and share the same vertex in the results.
+    ///
+    /// The default implementation calls `get_vertex` on the distinct ids,
+    /// where `params.limit` is ignored as it would break the alignment.
+    fn get_vertex_ordered(
+        &self, ids: &[ID], params: &QueryParams<Vertex>,
+    ) -> DynResult<Vec<Option<Vertex>>> {
+        let mut seen = HashSet::with_capacity(ids.len());
+        let distinct_ids: Vec<ID> = ids.iter().filter(|id| seen.insert(**id)).cloned().collect();
+        let mut vertices: HashMap<ID, Vertex> = HashMap::with_capacity(distinct_ids.len());
+        let mut params = params.clone();
+        params.limit = None;
+        for v in self.get_vertex(&distinct_ids, &params)? {
+            vertices.insert(v.id, v);
+        }
+        Ok(ids.iter().map(|id| vertices.get(id).cloned()).collect())
+    }
+
+    /// Fetch the given properties of the vertices in a second phase, e.g., after the vertices
+    /// have been filtered by structure only. The result is keyed by vertex id, and a property
+    /// that is absent on a vertex is skipped.
+    ///
+    /// The default implementation calls `get_vertex` with `props` required.
+    fn get_vertex_properties(
+        &self, ids: &[ID], props: &[PropKey],
+    ) -> DynResult<HashMap<ID, Vec<(PropKey, Object)>>> {
+        let params = QueryParams::with_props_only(props.to_vec());
+        let mut result = HashMap::with_capacity(ids.len());
+        for v in self.get_vertex(ids, &params)? {
+            let mut values = Vec::with_capacity(props.len());
+            for prop in props {
+                if let Some(obj) = v.details().get_property(prop).and_then(|o| o.try_to_owned()) {
+                    values.push((prop.clone(), obj));
+                }
+            }
+            result.insert(v.id, values);
+        }
+        Ok(result)
+    }
+
+    /// Whether the storage supports the capability `cap`, which is none by default.
+    fn supports(&self, _cap: GraphCapability) -> bool {
+        false
+    }
+}
+
+/// Scan the vertices of `label` whose property `prop` equals to `value`, by calling `scan_vertex`
+/// on `label` and filtering the vertices by `value`, where `params.limit` is applied after the
+/// filtering. `prop` is added to `params.props` if some specific properties are required, so that
+/// it is loaded by the storages honoring `props`. It is the default of `index_scan_vertex()`, and
+/// can be used by the backends with an index on some of the properties to scan the others.
+pub fn scan_vertex_by_property<G: GraphProxy + ?Sized>(
+    graph: &G, label: &Label, prop: &PropKey, value: &Object, params: &QueryParams<Vertex>,
+) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+    let mut scan_params = params.clone();
+    scan_params.labels = vec![label.clone()];
+    scan_params.limit = None;
+    if let Some(props) = scan_params.props.as_mut() {
+        // an empty `props` indicates all properties, which includes `prop` already
+        if !props.is_empty() && !props.contains(prop) {
+            props.push(prop.clone());
+        }
+    }
+    let value = value.clone();
+    let prop = prop.clone();
+    let vertices = graph
+        .scan_vertex(&scan_params)?
+        .filter(move |v| v.details().get_property(&prop) == Some(value.as_borrow()));
+    if let Some(limit) = params.limit {
+        Ok(Box::new(vertices.take(limit)))
+    } else {
+        Ok(Box::new(vertices))
+    }
+}
+
+/// A `Statement` shared by the `prepare_explore_*` calls of `CachedGraph` with identical params,
+/// which is executed concurrently by the workers sharing it.
+struct SharedStatement<I, O> {
+    stmt: Arc<dyn Statement<I, O>>,
+}
+
+impl<I, O> Clone for SharedStatement<I, O> {
+    fn clone(&self) -> Self {
+        SharedStatement { stmt: self.stmt.clone() }
+    }
+}
+
+impl<I: Send + 'static, O: Send + 'static> Statement<I, O> for SharedStatement<I, O> {
+    fn exec(&self, next: I) -> DynResult<DynIter<O>> {
+        self.stmt.exec(next)
+    }
+
+    fn try_exec(&self, next: I) -> DynResult<TryIter<O>> {
+        self.stmt.try_exec(next)
+    }
+}
+
+/// The content of the params, along with the direction, identifying a statement compiled by
+/// `prepare_explore_*`. The filter is identified by the encoded `pb::FilterChain`s it is built from.
+#[derive(PartialEq, Hash)]
+struct StatementKey {
+    direction: Direction,
+    labels: Vec<Label>,
+    limit: Option<usize>,
+    props: Option<Vec<PropKey>>,
+    filter: Vec<Vec<u8>>,
+    partitions: Option<Vec<u64>>,
+    extra_params: Option<Vec<(String, Object)>>,
+    order_by: Vec<(PropKey, Order)>,
+}
+
+impl Eq for StatementKey {}
+
+impl StatementKey {
+    /// Get the key of `params`, or `None` if the filter of `params` can not be identified by content.
+    fn new<E: Element + Send + Sync>(
+        direction: Direction, params: &QueryParams<E>,
+    ) -> Option<Self> {
+        let filter = params.filter_content()?.to_vec();
+        let extra_params = params.extra_params.as_ref().map(|extra_params| {
+            let mut extra_params: Vec<(String, Object)> =
+                extra_params.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            extra_params.sort_by(|a, b| a.0.cmp(&b.0));
+            extra_params
+        });
+        Some(StatementKey {
+            direction,
+            labels: params.labels.clone(),
+            limit: params.limit,
+            props: params.props.clone(),
+            filter,
+            partitions: params.partitions.clone(),
+            extra_params,
+            order_by: params.order_by.clone(),
+        })
+    }
+}
+
+type StatementCache<O> = Mutex<HashMap<StatementKey, SharedStatement<ID, O>>>;
+
+/// The maximum number of statements cached for vertices or edges, beyond which the cache is cleared.
+const MAX_CACHED_STATEMENTS: usize = 1024;
+
+/// A `GraphProxy` caching the statements compiled by `prepare_explore_vertex` and
+/// `prepare_explore_edge` of the wrapped graph, keyed by the direction and the content of the
+/// params, so that the repeated expands with identical params compile the statement only once.
+/// The params with a filter that is not built from `pb::FilterChain`s are never cached.
+pub struct CachedGraph<G> {
+    graph: G,
+    vertex_stmts: StatementCache<Vertex>,
+    edge_stmts: StatementCache<Edge>,
+}
+
+impl<G: GraphProxy> CachedGraph<G> {
+    pub fn new(graph: G) -> Self {
+        CachedGraph {
+            graph,
+            vertex_stmts: Mutex::new(HashMap::new()),
+            edge_stmts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_or_prepare<E, O, F>(
+        cache: &StatementCache<O>, direction: Direction, params: &QueryParams<E>, prepare: F,
+    ) -> DynResult<Box<dyn Statement<ID, O>>>
+    where
+        E: Element + Send + Sync,
+        O: Send + 'static,
+        F: FnOnce() -> DynResult<Box<dyn Statement<ID, O>>>,
+    {
+        let key = match StatementKey::new(direction, params) {
+            Some(key) => key,
+            None => return prepare(),
+        };
+        let mut cache = cache.lock().map_err(|_| str_to_dyn_error("statement cache poisoned"))?;
+        if let Some(stmt) = cache.get(&key) {
+            return Ok(Box::new(stmt.clone()));
+        }
+        if cache.len() >= MAX_CACHED_STATEMENTS {
+            cache.clear();
+        }
+        let stmt = SharedStatement { stmt: Arc::from(prepare()?) };
+        cache.insert(key, stmt.clone());
+        Ok(Box::new(stmt))
+    }
+}
+
+impl<G: GraphProxy> GraphProxy for CachedGraph<G> {
+    fn scan_vertex(
+        &self, params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        self.graph.scan_vertex(params)
+    }
+
+    fn scan_edge(
+        &self, params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        self.graph.scan_edge(params)
+    }
+
+    fn get_vertex(
+        &self, ids: &[ID], params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        self.graph.get_vertex(ids, params)
+    }
+
+    fn get_edge(
+        &self, ids: &[ID], params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        self.graph.get_edge(ids, params)
+    }
+
+    fn prepare_explore_vertex(
+        &self, direction: Direction, params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Statement<ID, Vertex>>> {
+        Self::get_or_prepare(&self.vertex_stmts, direction, params, || {
+            self.graph.prepare_explore_vertex(direction, params)
+        })
+    }
+
+    fn prepare_explore_edge(
+        &self, direction: Direction, params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Statement<ID, Edge>>> {
+        Self::get_or_prepare(&self.edge_stmts, direction, params, || {
+            self.graph.prepare_explore_edge(direction, params)
+        })
+    }
+
+    fn get_edge_between(
+        &self, src: ID, dst: ID, params: &QueryParams<Edge>,
+    ) -> DynResult<Option<Edge>> {
+        self.graph.get_edge_between(src, dst, params)
+    }
+
+    fn index_scan_vertex(
+        &self, label: &Label, prop: &PropKey, value: &Object, params: &QueryParams<Vertex>,
+    ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        self.graph.index_scan_vertex(label, prop, value, params)
+    }
+
+    fn scan_edge_undirected(
+        &self, params: &QueryParams<Edge>,
+    ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        self.graph.scan_edge_undirected(params)
+    }
+
+    fn get_vertex_ordered(
+        &self, ids: &[ID], params: &QueryParams<Vertex>,
+    ) -> DynResult<Vec<Option<Vertex>>> {
+        self.graph.get_vertex_ordered(ids, params)
+    }
+
+    fn get_vertex_properties(
+        &self, ids: &[ID], props: &[PropKey],
+    ) -> DynResult<HashMap<ID, Vec<(PropKey, Object)>>> {
+        self.graph.get_vertex_properties(ids, props)
+    }
+
+    fn supports(&self, cap: GraphCapability) -> bool {
+        self.graph.supports(cap)
+    }
 }
 
 lazy_static! {
@@ -191,7 +792,28 @@ pub fn register_graph(graph: Arc<dyn GraphProxy>) {
     GRAPH_PROXY.store(ptr, Ordering::SeqCst);
 }
 
+thread_local! {
+    static LOCAL_GRAPH_PROXY: RefCell<Option<Arc<dyn GraphProxy>>> = RefCell::new(None);
+}
+
+/// Register a graph that is only visible to the current thread, which overrides the global one
+/// registered by `register_graph`. It is mainly used to isolate the graphs of parallel tests.
+/// Note that the graph is not visible to the threads spawned by the current thread, e.g., the
+/// workers of a job.
+pub fn register_graph_local(graph: Arc<dyn GraphProxy>) {
+    LOCAL_GRAPH_PROXY.with(|local| *local.borrow_mut() = Some(graph));
+}
+
+/// Clear the graph registered by `register_graph_local`,
+/// after which the current thread sees the global graph again.
+pub fn clear_graph_local() {
+    LOCAL_GRAPH_PROXY.with(|local| *local.borrow_mut() = None);
+}
+
 pub fn get_graph() -> Option<Arc<dyn GraphProxy>> {
+    if let Some(graph) = LOCAL_GRAPH_PROXY.with(|local| local.borrow().clone()) {
+        return Some(graph);
+    }
     let ptr = GRAPH_PROXY.load(Ordering::SeqCst);
     if ptr.is_null() {
         None
@@ -199,3 +821,538 @@ pub fn get_graph() -> Option<Arc<dyn GraphProxy>> {
         Some(unsafe { (*ptr).clone() })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structure::{DefaultDetails, DynDetails};
+    use std::sync::atomic::AtomicUsize;
+
+    fn person(id: ID, name: &str, age: i32) -> Vertex {
+        let mut props = HashMap::new();
+        props.insert("name".into(), name.into());
+        props.insert("age".into(), age.into());
+        Vertex::new(id, Some(Label::Id(0)), DefaultDetails::new_with_prop(id, Label::Id(0), props))
+    }
+
+    fn filter_node(prop: &str, cmp: pb::Compare, value: pb_common::value::Item) -> pb::FilterNode {
+        filter_node_by_key(pb_common::key::Item::Name(prop.to_string()), cmp, value)
+    }
+
+    fn filter_node_by_key(
+        key: pb_common::key::Item, cmp: pb::Compare, value: pb_common::value::Item,
+    ) -> pb::FilterNode {
+        let exp = pb::FilterExp {
+            left: Some(pb_common::Key { item: Some(key) }),
+            cmp: cmp as i32,
+            right: Some(pb_common::Value { item: Some(value) }),
+        };
+        pb::FilterNode {
+            next: pb::Connect::Or as i32,
+            inner: Some(pb::filter_node::Inner::Single(exp)),
+        }
+    }
+
+    #[test]
+    fn and_filter_test() {
+        let marko = person(1, "marko", 29);
+        let vadas = person(2, "vadas", 27);
+        let josh = person(4, "josh", 32);
+        let mut params = QueryParams::<Vertex>::default();
+
+        // name == "marko" || name == "vadas"
+        let name_filter = pb::FilterChain {
+            node: vec![
+                filter_node(
+                    "name",
+                    pb::Compare::Eq,
+                    pb_common::value::Item::Str("marko".to_string()),
+                ),
+                filter_node(
+                    "name",
+                    pb::Compare::Eq,
+                    pb_common::value::Item::Str("vadas".to_string()),
+                ),
+            ],
+        };
+        params.and_filter(&name_filter).unwrap();
+        let filter = params.filter.as_ref().unwrap();
+        assert_eq!(filter.test(&marko), Some(true));
+        assert_eq!(filter.test(&vadas), Some(true));
+        assert_eq!(filter.test(&josh), Some(false));
+
+        // (name == "marko" || name == "vadas") && age < 29
+        let age_filter = pb::FilterChain {
+            node: vec![filter_node("age", pb::Compare::Lt, pb_common::value::Item::I32(29))],
+        };
+        let shared = params.clone();
+        params.and_filter(&age_filter).unwrap();
+        let filter = params.filter.as_ref().unwrap();
+        assert_eq!(filter.test(&marko), Some(false));
+        assert_eq!(filter.test(&vadas), Some(true));
+        assert_eq!(filter.test(&josh), Some(false));
+
+        // the filter shared before is not affected
+        let filter = shared.filter.as_ref().unwrap();
+        assert_eq!(filter.test(&marko), Some(true));
+        assert_eq!(filter.test(&vadas), Some(true));
+    }
+
+    #[test]
+    fn order_by_test() {
+        let params = QueryParams::<Vertex>::default();
+        assert!(params.order_by.is_empty());
+        assert!(!params.is_queryable());
+
+        let order_by =
+            |name: &str, order: pb::order_by_compare_pair::Order| pb::query_params::OrderBy {
+                key: Some(pb_common::PropertyKey {
+                    item: Some(pb_common::property_key::Item::Name(name.to_string())),
+                }),
+                order: order as i32,
+            };
+        let params_pb = pb::QueryParams {
+            order_by: vec![
+                order_by("age", pb::order_by_compare_pair::Order::Desc),
+                order_by("name", pb::order_by_compare_pair::Order::Asc),
+            ],
+            ..Default::default()
+        };
+        let params = QueryParams::<Vertex>::from_pb(Some(params_pb)).unwrap();
+        assert_eq!(params.order_by, vec![("age".into(), Order::Desc), ("name".into(), Order::Asc)]);
+        assert!(params.is_queryable());
+
+        // shuffle makes no sense for the results of storages;
+        let params_pb = pb::QueryParams {
+            order_by: vec![order_by("age", pb::order_by_compare_pair::Order::Shuffle)],
+            ..Default::default()
+        };
+        assert!(QueryParams::<Vertex>::from_pb(Some(params_pb)).is_err());
+    }
+
+    #[test]
+    fn needs_properties_test() {
+        let params = QueryParams::<Vertex>::default();
+        assert!(!params.needs_properties());
+
+        let all_props = pb::PropKeys { prop_keys: vec![], is_all: true };
+        let params =
+            QueryParams::<Vertex>::default().with_required_properties(Some(all_props)).unwrap();
+        assert_eq!(params.props, Some(vec![]));
+        assert!(params.needs_properties());
+
+        let some_props = pb::PropKeys {
+            prop_keys: vec![pb_common::PropertyKey {
+                item: Some(pb_common::property_key::Item::Name("age".to_string())),
+            }],
+            is_all: false,
+        };
+        let params =
+            QueryParams::<Vertex>::default().with_required_properties(Some(some_props)).unwrap();
+        assert_eq!(params.props, Some(vec!["age".into()]));
+        assert!(params.needs_properties());
+    }
+
+    #[test]
+    fn with_labels_only_test() {
+        let params = QueryParams::<Vertex>::with_labels_only(vec![Label::Id(0), Label::Id(1)]);
+        assert_eq!(params.labels, vec![Label::Id(0), Label::Id(1)]);
+        assert!(params.is_queryable());
+        assert!(!params.needs_properties());
+        assert!(params.filter.is_none() && params.limit.is_none());
+
+        let params = QueryParams::<Vertex>::with_labels_only(vec![]);
+        assert!(!params.is_queryable());
+
+        let params = QueryParams::<Edge>::with_props_only(vec!["weight".into()]);
+        assert!(params.labels.is_empty());
+        assert_eq!(params.props, Some(vec!["weight".into()]));
+        assert!(params.needs_properties());
+    }
+
+    #[test]
+    fn extract_label_eq_test() {
+        let label_key = || pb_common::key::Item::Label(pb_common::LabelKey {});
+        let mut params = QueryParams::<Vertex>::default();
+        assert_eq!(params.extract_label_eq(), None);
+
+        // label == 1
+        let label_filter = pb::FilterChain {
+            node: vec![filter_node_by_key(
+                label_key(),
+                pb::Compare::Eq,
+                pb_common::value::Item::I32(1),
+            )],
+        };
+        params.and_filter(&label_filter).unwrap();
+        assert_eq!(params.extract_label_eq(), Some(Label::Id(1)));
+
+        // label == 1 && age < 29
+        let age_filter = pb::FilterChain {
+            node: vec![filter_node("age", pb::Compare::Lt, pb_common::value::Item::I32(29))],
+        };
+        params.and_filter(&age_filter).unwrap();
+        assert_eq!(params.extract_label_eq(), None);
+
+        // label != 1
+        let mut params = QueryParams::<Vertex>::default();
+        let label_filter = pb::FilterChain {
+            node: vec![filter_node_by_key(
+                label_key(),
+                pb::Compare::Ne,
+                pb_common::value::Item::I32(1),
+            )],
+        };
+        params.and_filter(&label_filter).unwrap();
+        assert_eq!(params.extract_label_eq(), None);
+
+        // age < 29
+        let mut params = QueryParams::<Vertex>::default();
+        params.and_filter(&age_filter).unwrap();
+        assert_eq!(params.extract_label_eq(), None);
+    }
+
+    /// A mock graph of the given vertices and edges, which records the calls to the storage.
+    /// If built with vertices, it supports the index scan by a fake index on the property "name".
+    #[derive(Default)]
+    struct MockGraph {
+        vertices: Vec<Vertex>,
+        edges: Vec<Edge>,
+        index: HashMap<String, Vec<usize>>,
+        capabilities: HashSet<GraphCapability>,
+        index_hits: AtomicUsize,
+        fetched_ids: Mutex<Vec<ID>>,
+        scanned_props: Mutex<Vec<Option<Vec<PropKey>>>>,
+        compiled: Arc<AtomicUsize>,
+    }
+
+    impl MockGraph {
+        fn with_vertices(vertices: Vec<Vertex>) -> Self {
+            let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+            for (i, v) in vertices.iter().enumerate() {
+                if let Some(name) = v.details().get_property(&"name".into()) {
+                    index.entry(name.as_str().unwrap().into_owned()).or_default().push(i);
+                }
+            }
+            let capabilities = vec![GraphCapability::IndexScan].into_iter().collect();
+            MockGraph { vertices, index, capabilities, ..Default::default() }
+        }
+
+        fn with_edges(edges: Vec<(ID, ID, ID)>) -> Self {
+            let edges = edges
+                .into_iter()
+                .map(|(id, src, dst)| {
+                    let details = DynDetails::new(DefaultDetails::new(id, Label::Id(0)));
+                    Edge::new(id, Some(Label::Id(0)), src, dst, details)
+                })
+                .collect();
+            MockGraph { edges, ..Default::default() }
+        }
+    }
+
+    impl GraphProxy for MockGraph {
+        fn scan_vertex(
+            &self, params: &QueryParams<Vertex>,
+        ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+            self.scanned_props.lock().unwrap().push(params.props.clone());
+            let vertices = self.vertices.clone().into_iter();
+            Ok(Box::new(vertices.take(params.limit.unwrap_or(usize::MAX))))
+        }
+
+        fn scan_edge(
+            &self, params: &QueryParams<Edge>,
+        ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+            let edges = self.edges.clone().into_iter();
+            Ok(Box::new(edges.take(params.limit.unwrap_or(usize::MAX))))
+        }
+
+        fn get_vertex(
+            &self, ids: &[ID], _params: &QueryParams<Vertex>,
+        ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+            self.fetched_ids.lock().unwrap().extend_from_slice(ids);
+            // the vertices are returned in the order of storage
+            let vertices: Vec<Vertex> =
+                self.vertices.iter().filter(|v| ids.contains(&v.id)).cloned().collect();
+            Ok(Box::new(vertices.into_iter()))
+        }
+
+        fn get_edge(
+            &self, ids: &[ID], _params: &QueryParams<Edge>,
+        ) -> DynResult<Box<dyn Iterator<Item = Edge> + Send>> {
+            let edges: Vec<Edge> =
+                self.edges.iter().filter(|e| ids.contains(&e.id)).cloned().collect();
+            Ok(Box::new(edges.into_iter()))
+        }
+
+        /// Expand to the destinations of the edges from the vertex, regardless of `direction`
+        fn prepare_explore_vertex(
+            &self, _direction: Direction, _params: &QueryParams<Vertex>,
+        ) -> DynResult<Box<dyn Statement<ID, Vertex>>> {
+            self.compiled.fetch_add(1, Ordering::SeqCst);
+            let edges = self.edges.clone();
+            Ok(crate::graph_proxy::from_fn(move |id: ID| {
+                let neighbors: Vec<Vertex> = edges
+                    .iter()
+                    .filter(|e| e.src_id == id)
+                    .map(|e| {
+                        let details = DefaultDetails::new(e.dst_id, Label::Id(0));
+                        Vertex::new(e.dst_id, Some(Label::Id(0)), details)
+                    })
+                    .collect();
+                Ok(Box::new(neighbors.into_iter()) as DynIter<Vertex>)
+            }))
+        }
+
+        /// Expand to the edges from the vertex, regardless of `direction`
+        fn prepare_explore_edge(
+            &self, _direction: Direction, _params: &QueryParams<Edge>,
+        ) -> DynResult<Box<dyn Statement<ID, Edge>>> {
+            self.compiled.fetch_add(1, Ordering::SeqCst);
+            let edges = self.edges.clone();
+            Ok(crate::graph_proxy::from_fn(move |id: ID| {
+                let edges: Vec<Edge> = edges.iter().filter(|e| e.src_id == id).cloned().collect();
+                Ok(Box::new(edges.into_iter()) as DynIter<Edge>)
+            }))
+        }
+
+        fn index_scan_vertex(
+            &self, label: &Label, prop: &PropKey, value: &Object, params: &QueryParams<Vertex>,
+        ) -> DynResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+            if prop != &PropKey::from("name") {
+                return scan_vertex_by_property(self, label, prop, value, params);
+            }
+            self.index_hits.fetch_add(1, Ordering::SeqCst);
+            let name = value.as_str().map_err(|e| crate::str_to_dyn_error(&format!("{}", e)))?;
+            let vertices: Vec<Vertex> = self
+                .index
+                .get(name.as_ref())
+                .map(|indices| indices.iter().map(|i| self.vertices[*i].clone()).collect())
+                .unwrap_or_default();
+            Ok(Box::new(vertices.into_iter()))
+        }
+
+        fn supports(&self, cap: GraphCapability) -> bool {
+            self.capabilities.contains(&cap)
+        }
+    }
+
+    #[test]
+    fn index_scan_vertex_test() {
+        let persons = vec![person(1, "marko", 29), person(2, "vadas", 27), person(4, "marko", 32)];
+        let graph = MockGraph::with_vertices(persons);
+        let name = PropKey::from("name");
+        let params = QueryParams::default();
+
+        let ids: Vec<ID> = graph
+            .index_scan_vertex(&Label::Id(0), &name, &"marko".into(), &params)
+            .unwrap()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec![1, 4]);
+        assert_eq!(graph.index_hits.load(Ordering::SeqCst), 1);
+
+        let ids: Vec<ID> = graph
+            .index_scan_vertex(&Label::Id(0), &name, &"josh".into(), &params)
+            .unwrap()
+            .map(|v| v.id)
+            .collect();
+        assert!(ids.is_empty());
+        assert_eq!(graph.index_hits.load(Ordering::SeqCst), 2);
+        assert!(graph.scanned_props.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn scan_vertex_by_property_test() {
+        let persons = vec![person(1, "marko", 29), person(2, "vadas", 27), person(4, "josh", 29)];
+        let graph = MockGraph::with_vertices(persons);
+        let age = PropKey::from("age");
+        let scan = |params: &QueryParams<Vertex>| -> Vec<ID> {
+            graph
+                .index_scan_vertex(&Label::Id(0), &age, &29.into(), params)
+                .unwrap()
+                .map(|v| v.id)
+                .collect()
+        };
+
+        // "age" is not indexed, and is scanned without the index
+        assert_eq!(scan(&QueryParams::default()), vec![1, 4]);
+        assert_eq!(graph.index_hits.load(Ordering::SeqCst), 0);
+        // the limit applies after the filtering
+        assert_eq!(scan(&QueryParams { limit: Some(1), ..Default::default() }), vec![1]);
+        // "age" is loaded along with the required properties
+        assert_eq!(scan(&QueryParams::with_props_only(vec!["name".into()])), vec![1, 4]);
+        assert_eq!(scan(&QueryParams::with_props_only(vec![])), vec![1, 4]);
+        let scanned_props = graph.scanned_props.lock().unwrap().clone();
+        assert_eq!(
+            scanned_props,
+            vec![None, None, Some(vec!["name".into(), "age".into()]), Some(vec![])]
+        );
+    }
+
+    #[test]
+    fn supports_test() {
+        let graph = MockGraph::with_vertices(vec![person(1, "marko", 29)]);
+        assert!(graph.supports(GraphCapability::IndexScan));
+        assert!(!graph.supports(GraphCapability::CountPushdown));
+
+        let graph = CachedGraph::new(graph);
+        assert!(graph.supports(GraphCapability::IndexScan));
+        assert!(!graph.supports(GraphCapability::LimitPushdown));
+
+        let graph = MockGraph::with_edges(vec![]);
+        assert!(!graph.supports(GraphCapability::IndexScan));
+    }
+
+    #[test]
+    fn get_vertex_ordered_test() {
+        let persons = vec![person(1, "marko", 29), person(2, "vadas", 27), person(4, "josh", 32)];
+        let graph = MockGraph::with_vertices(persons);
+        let vertices = graph.get_vertex_ordered(&[4, 2, 2, 3], &QueryParams::default()).unwrap();
+        let ids: Vec<Option<ID>> = vertices.iter().map(|v| v.as_ref().map(|v| v.id)).collect();
+        assert_eq!(ids, vec![Some(4), Some(2), Some(2), None]);
+        // the duplicated id is fetched only once
+        assert_eq!(*graph.fetched_ids.lock().unwrap(), vec![4, 2, 3]);
+    }
+
+    #[test]
+    fn cached_prepare_explore_vertex_test() {
+        let inner = MockGraph::with_edges(vec![(0, 1, 2), (1, 2, 3)]);
+        let compiled = inner.compiled.clone();
+        let graph = CachedGraph::new(inner);
+        let mut params = QueryParams::<Vertex>::with_labels_only(vec![Label::Id(0)]);
+
+        let stmt1 = graph.prepare_explore_vertex(Direction::Out, &params).unwrap();
+        let stmt2 = graph.prepare_explore_vertex(Direction::Out, &params.clone()).unwrap();
+        assert_eq!(compiled.load(Ordering::SeqCst), 1);
+        assert_eq!(stmt1.exec(1).unwrap().map(|v| v.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(stmt2.exec(2).unwrap().map(|v| v.id).collect::<Vec<_>>(), vec![3]);
+
+        // either a different direction or different params is compiled separately
+        graph.prepare_explore_vertex(Direction::In, &params).unwrap();
+        assert_eq!(compiled.load(Ordering::SeqCst), 2);
+        params.limit = Some(1);
+        graph.prepare_explore_vertex(Direction::Out, &params).unwrap();
+        assert_eq!(compiled.load(Ordering::SeqCst), 3);
+
+        // the filters decoded separately from identical `pb::FilterChain`s share the statement
+        let name_filter = |name: &str| pb::FilterChain {
+            node: vec![filter_node(
+                "name",
+                pb::Compare::Eq,
+                pb_common::value::Item::Str(name.to_string()),
+            )],
+        };
+        let mut marko1 = QueryParams::<Vertex>::default();
+        marko1.and_filter(&name_filter("marko")).unwrap();
+        let mut marko2 = QueryParams::<Vertex>::default();
+        marko2.and_filter(&name_filter("marko")).unwrap();
+        graph.prepare_explore_vertex(Direction::Out, &marko1).unwrap();
+        graph.prepare_explore_vertex(Direction::Out, &marko2).unwrap();
+        assert_eq!(compiled.load(Ordering::SeqCst), 4);
+        let mut vadas = QueryParams::<Vertex>::default();
+        vadas.and_filter(&name_filter("vadas")).unwrap();
+        graph.prepare_explore_vertex(Direction::Out, &vadas).unwrap();
+        assert_eq!(compiled.load(Ordering::SeqCst), 5);
+
+        // a filter assigned directly can not be identified, and is never cached
+        marko2.filter = vadas.filter.clone();
+        graph.prepare_explore_vertex(Direction::Out, &marko2).unwrap();
+        graph.prepare_explore_vertex(Direction::Out, &marko2).unwrap();
+        assert_eq!(compiled.load(Ordering::SeqCst), 7);
+    }
+
+    #[test]
+    fn scan_edge_undirected_test() {
+        // an undirected edge stored as two half-edges, and a self-loop
+        let graph = MockGraph::with_edges(vec![(0, 2, 1), (1, 1, 2), (2, 3, 3)]);
+        let mut params = QueryParams::<Edge>::default();
+        assert_eq!(graph.scan_edge(&params).unwrap().count(), 3);
+        let edges: Vec<(ID, ID)> =
+            graph.scan_edge_undirected(&params).unwrap().map(|e| (e.src_id, e.dst_id)).collect();
+        assert_eq!(edges, vec![(1, 2), (3, 3)]);
+        // the limit applies after the half-edge `2 -> 1` is filtered
+        params.limit = Some(1);
+        let edges: Vec<ID> = graph.scan_edge_undirected(&params).unwrap().map(|e| e.id).collect();
+        assert_eq!(edges, vec![1]);
+    }
+
+    #[test]
+    fn limit_statement_test() {
+        // a statement ignoring the limit, which expands 10 neighbors for each vertex
+        let stmt = crate::graph_proxy::from_fn(|id: ID| {
+            let neighbors = (0..10).map(move |i| {
+                let nbr = id * 100 + i;
+                Vertex::new(nbr, Some(Label::Id(0)), DefaultDetails::new(nbr, Label::Id(0)))
+            });
+            Ok(Box::new(neighbors) as DynIter<Vertex>)
+        });
+        let stmt = limit_statement(stmt, Some(3));
+        // the limit applies to each input vertex
+        for id in 1..3 {
+            let neighbors: Vec<ID> = stmt.exec(id).unwrap().map(|v| v.id).collect();
+            assert_eq!(neighbors, vec![id * 100, id * 100 + 1, id * 100 + 2]);
+        }
+        let stmt = limit_statement(stmt, None);
+        assert_eq!(stmt.exec(1).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn try_statement_test() {
+        // a statement whose storage read fails on the third neighbor of vertex 2
+        let stmt = crate::graph_proxy::from_fn(|id: ID| {
+            let neighbors = (0..5).map(move |i| {
+                if id == 2 && i == 2 {
+                    Err(str_to_dyn_error("storage read failure"))
+                } else {
+                    let nbr = id * 100 + i;
+                    Ok(Vertex::new(nbr, Some(Label::Id(0)), DefaultDetails::new(nbr, Label::Id(0))))
+                }
+            });
+            Ok(Box::new(neighbors) as DynIter<DynResult<Vertex>>)
+        });
+        let stmt = try_statement(stmt);
+        let neighbors: Vec<ID> = stmt.exec(1).unwrap().map(|v| v.id).collect();
+        assert_eq!(neighbors, vec![100, 101, 102, 103, 104]);
+        // the outputs are lazy, thus no error is met before the failed read
+        let mut outputs = stmt.try_exec(2).unwrap();
+        assert_eq!(outputs.next().unwrap().unwrap().id, 200);
+        assert_eq!(outputs.next().unwrap().unwrap().id, 201);
+        // the failed read is returned once it is reached, and stops the outputs
+        match outputs.next() {
+            Some(Err(e)) => assert_eq!(e.to_string(), "storage read failure"),
+            _ => panic!("the storage failure is not surfaced"),
+        }
+        assert!(outputs.next().is_none());
+        // and does not affect the other inputs
+        assert_eq!(stmt.try_exec(3).unwrap().count(), 5);
+        // while `exec` stops at the failed read
+        let neighbors: Vec<ID> = stmt.exec(2).unwrap().map(|v| v.id).collect();
+        assert_eq!(neighbors, vec![200, 201]);
+    }
+
+    fn scan_vertex_ids() -> Vec<ID> {
+        let graph = get_graph().unwrap();
+        graph.scan_vertex(&QueryParams::default()).unwrap().map(|v| v.id).collect()
+    }
+
+    #[test]
+    fn register_graph_local_test() {
+        let handles: Vec<_> = (1..3)
+            .map(|id| {
+                std::thread::spawn(move || {
+                    register_graph_local(Arc::new(MockGraph::with_vertices(vec![person(
+                        id, "marko", 29,
+                    )])));
+                    for _ in 0..100 {
+                        assert_eq!(scan_vertex_ids(), vec![id]);
+                    }
+                    clear_graph_local();
+                    assert!(LOCAL_GRAPH_PROXY.with(|local| local.borrow().is_none()));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
@@ -13,6 +13,7 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+mod async_graph;
 mod element;
 pub mod filter;
 mod graph;
@@ -21,12 +22,14 @@ mod property;
 use crate::generated::gremlin as pb;
 use crate::structure::codec::ParseError;
 use crate::FromPb;
+use std::str::FromStr;
+pub use async_graph::{AsyncGraphProxy, BlockingGraphProxy, DynStream};
 pub use element::{Edge, Element, GraphElement, Label, LabelId, Vertex, VertexOrEdge, ID, ID_BITS};
 pub use filter::*;
 pub use graph::*;
-pub use property::{DefaultDetails, Details, DynDetails, PropId, PropKey, Token};
+pub use property::{DefaultDetails, Details, DynDetails, FromProperty, PropId, PropKey, Token};
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Direction {
     Out = 0,
     In = 1,
@@ -46,6 +49,23 @@ impl FromPb<pb::Direction> for Direction {
     }
 }
 
+/// Parse the direction of the query languages, i.e., "out", "in" or "both", case-insensitively.
+impl FromStr for Direction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "out" => Ok(Direction::Out),
+            "in" => Ok(Direction::In),
+            "both" => Ok(Direction::Both),
+            _ => Err(ParseError::OtherErr(format!(
+                "unknown direction {:?}, expect one of \"out\", \"in\" or \"both\"",
+                s
+            ))),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum EndPointOpt {
     Out = 0,
@@ -66,6 +86,28 @@ impl FromPb<pb::edge_vertex_step::EndpointOpt> for EndPointOpt {
     }
 }
 
+/// The order of the results queried from the storages, see `QueryParams::order_by`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Order {
+    Asc = 0,
+    Desc = 1,
+}
+
+impl FromPb<pb::order_by_compare_pair::Order> for Order {
+    fn from_pb(order: pb::order_by_compare_pair::Order) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        match order {
+            pb::order_by_compare_pair::Order::Asc => Ok(Order::Asc),
+            pb::order_by_compare_pair::Order::Desc => Ok(Order::Desc),
+            pb::order_by_compare_pair::Order::Shuffle => {
+                Err(ParseError::OtherErr("shuffle is not supported in order_by".to_string()))
+            }
+        }
+    }
+}
+
 pub type Tag = u8;
 pub const EMPTY_TAG: Tag = 0;
 /// The initial number of tags, which happens to be a block of bitset.
@@ -85,3 +127,17 @@ impl FromPb<pb::StepTag> for Tag {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn direction_from_str_test() {
+        assert_eq!("out".parse::<Direction>().unwrap(), Direction::Out);
+        assert_eq!("In".parse::<Direction>().unwrap(), Direction::In);
+        assert_eq!("BOTH".parse::<Direction>().unwrap(), Direction::Both);
+        let err = "outE".parse::<Direction>().unwrap_err();
+        assert!(err.to_string().contains("unknown direction \"outE\""));
+    }
+}
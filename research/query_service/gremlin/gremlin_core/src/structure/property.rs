@@ -17,7 +17,8 @@ use crate::generated::common as common_pb;
 use crate::structure::codec::ParseError;
 use crate::structure::element::{read_id, write_id, Label};
 use crate::{FromPb, ID};
-use dyn_type::{BorrowObject, Object};
+use dyn_type::object::Primitives;
+use dyn_type::{BorrowObject, CastError, Object};
 use pegasus::codec::{Decode, Encode, ReadExt, WriteExt};
 use pegasus_common::downcast::*;
 use std::collections::HashMap;
@@ -64,6 +65,16 @@ impl From<PropId> for PropKey {
     }
 }
 
+impl PropKey {
+    pub fn to_pb(&self) -> common_pb::PropertyKey {
+        let item = match self {
+            PropKey::Str(name) => common_pb::property_key::Item::Name(name.clone()),
+            PropKey::Id(name_id) => common_pb::property_key::Item::NameId(*name_id as i32),
+        };
+        common_pb::PropertyKey { item: Some(item) }
+    }
+}
+
 impl FromPb<common_pb::PropertyKey> for PropKey {
     fn from_pb(prop_key: common_pb::PropertyKey) -> Result<Self, ParseError>
     where
@@ -118,6 +129,105 @@ pub trait Details: Send + Sync + AsAny {
     fn get_id(&self) -> ID;
 
     fn get_label(&self) -> &Label;
+
+    /// Get all the properties in any order, or `None` if the details can not enumerate the
+    /// properties, which is the default.
+    fn get_all_properties(&self) -> Option<Vec<(PropKey, Object)>> {
+        None
+    }
+}
+
+/// Encode the properties loaded in `details` into pb, which are none if `details` can not enumerate
+/// its properties.
+pub(crate) fn properties_to_pb(
+    details: &DynDetails,
+) -> Result<Vec<common_pb::Property>, ParseError> {
+    let properties = details.get_all_properties().unwrap_or_default();
+    let mut properties_pb = Vec::with_capacity(properties.len());
+    for (key, value) in properties {
+        let value = object_to_pb(&value)?;
+        properties_pb.push(common_pb::Property { key: Some(key.to_pb()), value: Some(value) });
+    }
+    Ok(properties_pb)
+}
+
+pub(crate) fn properties_from_pb(
+    properties_pb: Vec<common_pb::Property>,
+) -> Result<HashMap<PropKey, Object>, ParseError> {
+    let mut properties = HashMap::with_capacity(properties_pb.len());
+    for property in properties_pb {
+        let key =
+            property.key.ok_or(ParseError::OtherErr("key is missing in property".to_string()))?;
+        let value = property
+            .value
+            .ok_or(ParseError::OtherErr("value is missing in property".to_string()))?;
+        properties.insert(PropKey::from_pb(key)?, object_from_pb(value)?);
+    }
+    Ok(properties)
+}
+
+fn object_to_pb(value: &Object) -> Result<common_pb::Value, ParseError> {
+    let item = match value {
+        Object::Primitive(Primitives::Byte(v)) => common_pb::value::Item::I32(*v as i32),
+        Object::Primitive(Primitives::Integer(v)) => common_pb::value::Item::I32(*v),
+        Object::Primitive(Primitives::Long(v)) => common_pb::value::Item::I64(*v),
+        Object::Primitive(Primitives::Float(v)) => common_pb::value::Item::F64(*v),
+        Object::String(s) => common_pb::value::Item::Str(s.clone()),
+        Object::Blob(b) => common_pb::value::Item::Blob(b.to_vec()),
+        Object::Primitive(Primitives::ULLong(_)) | Object::DynOwned(_) => {
+            return Err(ParseError::OtherErr(format!(
+                "unsupported property value {:?} in pb",
+                value
+            )));
+        }
+    };
+    Ok(common_pb::Value { item: Some(item) })
+}
+
+fn object_from_pb(value: common_pb::Value) -> Result<Object, ParseError> {
+    match value.item {
+        Some(common_pb::value::Item::Boolean(b)) => Ok(b.into()),
+        Some(common_pb::value::Item::I32(i)) => Ok(i.into()),
+        Some(common_pb::value::Item::I64(i)) => Ok(i.into()),
+        Some(common_pb::value::Item::F64(f)) => Ok(f.into()),
+        Some(common_pb::value::Item::Str(s)) => Ok(s.into()),
+        Some(common_pb::value::Item::Blob(b)) => Ok(b.into()),
+        _ => Err(ParseError::OtherErr("unsupported property value in pb".to_string())),
+    }
+}
+
+/// A type that a property value can be cast into, see `DynDetails::get_property_as()`.
+pub trait FromProperty: Sized {
+    fn from_property(value: BorrowObject) -> Result<Self, CastError>;
+}
+
+macro_rules! impl_from_property {
+    ($ty: ty, $as_ty: ident) => {
+        impl FromProperty for $ty {
+            fn from_property(value: BorrowObject) -> Result<Self, CastError> {
+                value.$as_ty()
+            }
+        }
+    };
+}
+
+impl_from_property!(bool, as_bool);
+impl_from_property!(i32, as_i32);
+impl_from_property!(i64, as_i64);
+impl_from_property!(u32, as_u32);
+impl_from_property!(u64, as_u64);
+impl_from_property!(f64, as_f64);
+
+impl FromProperty for String {
+    fn from_property(value: BorrowObject) -> Result<Self, CastError> {
+        value.as_str().map(|s| s.into_owned())
+    }
+}
+
+impl FromProperty for Object {
+    fn from_property(value: BorrowObject) -> Result<Self, CastError> {
+        Ok(value.into())
+    }
 }
 
 #[derive(Clone)]
@@ -129,6 +239,12 @@ impl DynDetails {
     pub fn new<P: Details + 'static>(p: P) -> Self {
         DynDetails { inner: Arc::new(p) }
     }
+
+    /// Get the property of `key` cast into `T`, which is `Ok(None)` if the property does not exist,
+    /// or `Err` if it can not be cast into `T`.
+    pub fn get_property_as<T: FromProperty>(&self, key: &PropKey) -> Result<Option<T>, CastError> {
+        self.get_property(key).map(T::from_property).transpose()
+    }
 }
 
 impl_as_any!(DynDetails);
@@ -145,6 +261,14 @@ impl Details for DynDetails {
     fn get_label(&self) -> &Label {
         self.inner.get_label()
     }
+
+    /// Get all the properties sorted by keys, or `None` if the inner details can not enumerate
+    /// the properties.
+    fn get_all_properties(&self) -> Option<Vec<(PropKey, Object)>> {
+        let mut properties = self.inner.get_all_properties()?;
+        properties.sort_by(|a, b| a.0.cmp(&b.0));
+        Some(properties)
+    }
 }
 
 impl Encode for DynDetails {
@@ -223,6 +347,10 @@ impl Details for DefaultDetails {
     fn get_label(&self) -> &Label {
         &self.label
     }
+
+    fn get_all_properties(&self) -> Option<Vec<(PropKey, Object)>> {
+        Some(self.inner.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    }
 }
 
 impl Encode for DefaultDetails {
@@ -256,6 +384,7 @@ impl Decode for DefaultDetails {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::structure::Element;
 
     #[test]
     fn test_ser_dyn_details() {
@@ -274,4 +403,92 @@ mod test {
             unreachable!()
         }
     }
+
+    #[test]
+    fn test_get_property_as() {
+        let mut details = DefaultDetails::new(1, Label::Str("person".to_owned()));
+        details.insert("age".into(), 29.into());
+        details.insert("name".into(), "marko".into());
+        let vertex = crate::structure::Vertex::new(1, None, details);
+
+        assert_eq!(vertex.get_property_as::<i64>(&"age".into()).unwrap(), Some(29));
+        assert_eq!(
+            vertex.get_property_as::<String>(&"name".into()).unwrap(),
+            Some("marko".to_owned())
+        );
+        assert_eq!(vertex.get_property_as::<i32>(&"weight".into()).unwrap(), None);
+        assert!(vertex.get_property_as::<i32>(&"name".into()).is_err());
+    }
+
+    #[test]
+    fn test_ser_vertex_with_props() {
+        let mut details = DefaultDetails::new(1, Label::Id(0));
+        details.insert("age".into(), 29.into());
+        details.insert("name".into(), "marko".into());
+        let vertex = crate::structure::Vertex::new(1, Some(Label::Id(0)), details);
+        let mut bytes = vec![];
+        vertex.write_to(&mut bytes).unwrap();
+
+        let mut reader = &bytes[0..];
+        let de = <crate::structure::Vertex>::read_from(&mut reader).unwrap();
+        assert!(de == vertex);
+        assert_eq!(de.label, Some(Label::Id(0)));
+        assert_eq!(de.get_property_as::<i32>(&"age".into()).unwrap(), Some(29));
+        assert_eq!(de.get_property_as::<String>(&"name".into()).unwrap(), Some("marko".to_owned()));
+    }
+
+    #[test]
+    fn test_ser_edge_with_props() {
+        let mut details = DefaultDetails::new(7, Label::Id(1));
+        details.insert("weight".into(), 0.5.into());
+        details.insert(PropKey::Id(3), 2014.into());
+        let mut edge =
+            crate::structure::Edge::new(7, Some(Label::Id(1)), 1, 2, DynDetails::new(details));
+        edge.set_src_label(Label::Id(0));
+        let mut bytes = vec![];
+        edge.write_to(&mut bytes).unwrap();
+
+        let mut reader = &bytes[0..];
+        let de = <crate::structure::Edge>::read_from(&mut reader).unwrap();
+        assert!(de == edge);
+        assert_eq!((de.src_id, de.dst_id), (1, 2));
+        assert_eq!(de.get_src_label(), Some(&Label::Id(0)));
+        assert_eq!(de.get_dst_label(), None);
+        assert_eq!(de.get_property_as::<f64>(&"weight".into()).unwrap(), Some(0.5));
+        assert_eq!(de.get_property_as::<i32>(&PropKey::Id(3)).unwrap(), Some(2014));
+    }
+
+    #[test]
+    fn test_pb_vertex_with_props() {
+        // the id takes more than 64 bits
+        let id = (1 << 64) | 1;
+        let mut details = DefaultDetails::new(id, Label::Id(0));
+        details.insert("age".into(), 29.into());
+        details.insert("name".into(), "marko".into());
+        let vertex = crate::structure::Vertex::new(id, Some(Label::Id(0)), details);
+
+        let de = <crate::structure::Vertex>::from_pb(vertex.to_pb().unwrap()).unwrap();
+        assert!(de == vertex);
+        assert_eq!(de.label, Some(Label::Id(0)));
+        assert_eq!(de.details().get_all_properties(), vertex.details().get_all_properties());
+        assert_eq!(de.get_property_as::<i32>(&"age".into()).unwrap(), Some(29));
+        assert_eq!(de.get_property_as::<String>(&"name".into()).unwrap(), Some("marko".to_owned()));
+    }
+
+    #[test]
+    fn test_pb_edge_with_props() {
+        let mut details = DefaultDetails::new(7, Label::Str("knows".to_owned()));
+        details.insert("weight".into(), 0.5.into());
+        details.insert(PropKey::Id(3), 2014.into());
+        let mut edge = crate::structure::Edge::new(7, None, 1, 2, DynDetails::new(details));
+        edge.set_dst_label(Label::Id(0));
+
+        let de = <crate::structure::Edge>::from_pb(edge.to_pb().unwrap()).unwrap();
+        assert!(de == edge);
+        assert_eq!((de.src_id, de.dst_id), (1, 2));
+        assert_eq!(de.label, Some(Label::Str("knows".to_owned())));
+        assert_eq!(de.get_src_label(), None);
+        assert_eq!(de.get_dst_label(), Some(&Label::Id(0)));
+        assert_eq!(de.details().get_all_properties(), edge.details().get_all_properties());
+    }
 }
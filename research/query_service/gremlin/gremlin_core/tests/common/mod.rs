@@ -30,7 +30,9 @@ pub mod test {
     use gremlin_core::process::traversal::step::result_downcast::{
         try_downcast_list, try_downcast_pair,
     };
-    use gremlin_core::process::traversal::step::{graph_step_from, ResultProperty};
+    use gremlin_core::process::traversal::step::{
+        graph_step_from, PropertyElement, ResultProperty,
+    };
     use gremlin_core::process::traversal::traverser::{Requirement, Traverser};
     use gremlin_core::structure::{Details, PropKey, Tag, VertexOrEdge};
     use gremlin_core::{create_demo_graph, str_to_dyn_error, DynIter, Element, Partitioner, ID};
@@ -80,6 +82,8 @@ pub mod test {
         expected_tag_props: Option<Vec<Vec<(Tag, Vec<(PropKey, Object)>)>>>,
         // to test early stop, with the expected value of number of results
         expected_result_num: Option<usize>,
+        // to test the result of properties step
+        expected_property_elements: Option<Vec<PropertyElement>>,
     }
 
     impl TestJobFactory {
@@ -96,6 +100,7 @@ pub mod test {
                 expected_path_len: None,
                 expected_tag_props: None,
                 expected_result_num: None,
+                expected_property_elements: None,
             }
         }
 
@@ -149,6 +154,14 @@ pub mod test {
             factory
         }
 
+        pub fn with_expect_property_elements(
+            expected_property_elements: Vec<PropertyElement>,
+        ) -> Self {
+            let mut factory = TestJobFactory::new();
+            factory.expected_property_elements = Some(expected_property_elements);
+            factory
+        }
+
         pub fn set_ordered(&mut self, ordered: bool) {
             self.is_ordered = ordered;
         }
@@ -165,7 +178,7 @@ pub mod test {
             if let Some(source) = plan.source.as_ref() {
                 let source = input.input_from(self.gen_source(source.resource.as_ref())?)?;
                 let stream = if let Some(task) = plan.plan.as_ref() {
-                    self.inner.install(source, &task.plan)?
+                    self.inner.install_with(source, &task.plan, self.requirement)?
                 } else {
                     source
                 };
@@ -178,7 +191,7 @@ pub mod test {
                             AccumKind::Cnt => stream
                                 .count()?
                                 .into_stream()?
-                                .map(|v| Ok(Traverser::Object(v.into())))?
+                                .map(|v| Ok(Traverser::object(v)))?
                                 .sink_into(output),
                             _ => todo!(),
                         }
@@ -219,6 +232,7 @@ pub mod test {
             let mut path_result = vec![];
             let mut map_result = vec![];
             let mut tag_result = vec![];
+            let mut property_result = vec![];
             for traverser in result.iter() {
                 if let Some(element) = traverser.get_element() {
                     if let Some(property_opt) = self.expected_properties.clone() {
@@ -257,6 +271,8 @@ pub mod test {
                                     path.push(item.as_element().expect("element").id());
                                 }
                                 path_result.push(path);
+                            } else if let Some(property) = x.try_downcast_ref::<PropertyElement>() {
+                                property_result.push(property.clone());
                             } else if let Some(result_prop) = x.try_downcast_ref::<ResultProperty>()
                             {
                                 let mut tag_entries = vec![];
@@ -319,6 +335,8 @@ pub mod test {
                 assert_eq!(self.expected_tag_props.as_ref().unwrap(), &tag_result);
             } else if self.expected_values.is_some() {
                 assert_eq!(self.expected_values.as_ref().unwrap(), &obj_result);
+            } else if self.expected_property_elements.is_some() {
+                assert_eq!(self.expected_property_elements.as_ref().unwrap(), &property_result);
             } else {
                 println!("no expected values specified in test");
             }
@@ -18,6 +18,15 @@ mod common;
 #[cfg(test)]
 mod test {
     use crate::common::test::*;
+    use bit_set::BitSet;
+    use gremlin_core::process::traversal::step::DedupStep;
+    use gremlin_core::process::traversal::traverser::{Requirement, Traverser};
+    use gremlin_core::structure::{DefaultDetails, DynDetails, Edge, Label, Vertex};
+    use gremlin_core::{Element, ID};
+    use pegasus::api::function::FnResult;
+    use pegasus::api::{CorrelatedSubTask, Count, Map, Sink};
+    use pegasus::JobConf;
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     // g.V().union(identity(),identity()).dedup()
@@ -29,4 +38,104 @@ mod test {
         let pb_request = read_pb_request(gen_path("dedup_step_test_01")).expect("read pb failed");
         run_test(test_job_factory, pb_request);
     }
+
+    #[test]
+    fn dedup_element_by_id_test() {
+        let marko = Vertex::new(1, Some(Label::Id(0)), DefaultDetails::new(1, Label::Id(0)));
+        let mut props = HashMap::new();
+        props.insert("name".into(), "marko".into());
+        let marko_with_props = Vertex::new(
+            1,
+            Some(Label::Id(0)),
+            DefaultDetails::new_with_prop(1, Label::Id(0), props),
+        );
+        let vadas = Vertex::new(2, Some(Label::Id(0)), DefaultDetails::new(2, Label::Id(0)));
+        let vertices: HashSet<Vertex> = vec![marko, marko_with_props, vadas].into_iter().collect();
+        assert_eq!(vertices.len(), 2);
+
+        let knows = |id| {
+            Edge::new(
+                id,
+                Some(Label::Id(0)),
+                1,
+                2,
+                DynDetails::new(DefaultDetails::new(id, Label::Id(0))),
+            )
+        };
+        let edges: HashSet<Edge> = vec![knows(7), knows(7), knows(8)].into_iter().collect();
+        assert_eq!(edges.len(), 2);
+    }
+
+    fn vertex(id: ID) -> Vertex {
+        Vertex::new(id, Some(Label::Id(0)), DefaultDetails::new(id, Label::Id(0)))
+    }
+
+    fn head_id(t: Traverser) -> FnResult<ID> {
+        Ok(t.get_element().unwrap().id())
+    }
+
+    #[test]
+    fn dedup_by_head_test() {
+        initialize();
+        let conf = JobConf::new("dedup_by_head_test");
+        let result = pegasus::run(conf, || {
+            |input, output| {
+                let src = vec![1, 2, 1, 3, 2, 1].into_iter().map(|id| Traverser::new(vertex(id)));
+                let stream = DedupStep::new(None).install(input.input_from(src)?)?;
+                stream.map(head_id)?.sink_into(output)
+            }
+        })
+        .expect("submit job failure");
+        let ids: Vec<_> = result.map(|id| id.unwrap()).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn dedup_by_tag_test() {
+        initialize();
+        let conf = JobConf::new("dedup_by_tag_test");
+        let result = pegasus::run(conf, || {
+            |input, output| {
+                // g.V().as("a").out().dedup("a"), where tag "a" is 1
+                let mut tags = BitSet::new();
+                tags.insert(1);
+                let src = vec![(1, 10), (1, 11), (2, 10)].into_iter().map(move |(a, head)| {
+                    let mut t = Traverser::with_path(vertex(a), &tags, Requirement::LABELED_PATH);
+                    t.split(vertex(head), &BitSet::new());
+                    t
+                });
+                let stream = DedupStep::new(Some(1)).install(input.input_from(src)?)?;
+                stream.map(head_id)?.sink_into(output)
+            }
+        })
+        .expect("submit job failure");
+        let ids: Vec<_> = result.map(|id| id.unwrap()).collect();
+        assert_eq!(ids, vec![10, 10]);
+    }
+
+    #[test]
+    fn dedup_per_scope_test() {
+        initialize();
+        let conf = JobConf::new("dedup_per_scope_test");
+        let result = pegasus::run(conf, || {
+            |input, output| {
+                let src = vec![1, 2].into_iter().map(|id| Traverser::new(vertex(id)));
+                input
+                    .input_from(src)?
+                    .apply(|sub| {
+                        let neighbors = sub.flat_map(|_| {
+                            Ok(vec![10, 11, 10].into_iter().map(|id| Traverser::new(vertex(id))))
+                        })?;
+                        DedupStep::new(None).install(neighbors)?.count()
+                    })?
+                    .map(|(parent, cnt)| Ok((head_id(parent)?, cnt)))?
+                    .sink_into(output)
+            }
+        })
+        .expect("submit job failure");
+        let mut counts: Vec<_> = result.map(|cnt| cnt.unwrap()).collect();
+        counts.sort();
+        // each scope is deduplicated separately
+        assert_eq!(counts, vec![(1, 2), (2, 2)]);
+    }
 }
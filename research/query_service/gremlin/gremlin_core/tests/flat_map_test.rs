@@ -18,6 +18,7 @@ mod common;
 #[cfg(test)]
 mod test {
     use crate::common::test::*;
+    use gremlin_core::process::traversal::step::PropertyElement;
 
     // g.V().out()
     #[test]
@@ -94,4 +95,20 @@ mod test {
         let pb_request = read_pb_request(gen_path("values_step_test_01")).expect("read pb failed");
         run_test(test_job_factory, pb_request);
     }
+
+    // g.V().properties("id", "name")
+    #[test]
+    fn properties_step_test_01() {
+        initialize();
+        let names = vec!["marko", "vadas", "lop", "josh", "ripple", "peter"];
+        let mut expected = vec![];
+        for (id, name) in (1..=6).zip(names) {
+            expected.push(PropertyElement { key: "id".into(), value: id.into() });
+            expected.push(PropertyElement { key: "name".into(), value: name.into() });
+        }
+        let test_job_factory = TestJobFactory::with_expect_property_elements(expected);
+        let pb_request =
+            read_pb_request(gen_path("properties_step_test_01")).expect("read pb failed");
+        run_test(test_job_factory, pb_request);
+    }
 }
@@ -0,0 +1,133 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+mod common;
+
+#[cfg(test)]
+mod test {
+    use crate::common::test::*;
+    use dyn_type::Object;
+    use graph_store::config::JsonConf;
+    use graph_store::prelude::LDBCGraphSchema;
+    use gremlin_core::structure::{Edge, Label, PropKey, QueryParams, Vertex};
+    use gremlin_core::{create_demo_graph, get_graph, ID};
+
+    #[test]
+    fn get_vertex_properties_test() {
+        create_demo_graph();
+        let graph = get_graph().unwrap();
+        let ids = to_global_ids(vec![1, 2]);
+        let props: Vec<PropKey> = vec!["name".into(), "age".into()];
+        let result = graph.get_vertex_properties(&ids, &props).unwrap();
+        assert_eq!(result.len(), 2);
+        let expected: Vec<(PropKey, Object)> =
+            vec![("name".into(), "marko".into()), ("age".into(), 29.into())];
+        assert_eq!(result.get(&ids[0]).unwrap(), &expected);
+        let expected: Vec<(PropKey, Object)> =
+            vec![("name".into(), "vadas".into()), ("age".into(), 27.into())];
+        assert_eq!(result.get(&ids[1]).unwrap(), &expected);
+    }
+
+    #[test]
+    fn get_edge_between_test() {
+        create_demo_graph();
+        let graph = get_graph().unwrap();
+        let (v1, v2) = (to_global_id(1) as ID, to_global_id(2) as ID);
+        let params = QueryParams::<Edge>::default();
+        let edge = graph.get_edge_between(v1, v2, &params).unwrap().expect("edge 1->2 not found");
+        assert_eq!((edge.src_id, edge.dst_id), (v1, v2));
+        // edges are outgoing from `src`
+        assert!(graph.get_edge_between(v2, v1, &params).unwrap().is_none());
+        // 1-knows->2, but not 1-created->2
+        let mut params = QueryParams::<Edge>::default();
+        params.labels = vec![Label::Id(1)];
+        assert!(graph.get_edge_between(v1, v2, &params).unwrap().is_none());
+    }
+
+    #[test]
+    fn index_scan_vertex_test() {
+        create_demo_graph();
+        let graph = get_graph().unwrap();
+        let mut params = QueryParams::<Vertex>::default();
+        // the demo graph only scans with partitions given
+        params.partitions = Some(vec![0]);
+        let ids: Vec<ID> = graph
+            .index_scan_vertex(&Label::Id(0), &"name".into(), &"marko".into(), &params)
+            .unwrap()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec![to_global_id(1) as ID]);
+        // the person "josh" is 32, and the limit applies after the filtering
+        params.limit = Some(1);
+        let ids: Vec<ID> = graph
+            .index_scan_vertex(&Label::Id(0), &"age".into(), &32.into(), &params)
+            .unwrap()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec![to_global_id(4) as ID]);
+        // "lop" is a software
+        let mut vertices =
+            graph.index_scan_vertex(&Label::Id(0), &"name".into(), &"lop".into(), &params).unwrap();
+        assert!(vertices.next().is_none());
+    }
+
+    fn modern_schema() -> LDBCGraphSchema {
+        let schema_json = r#"
+        {
+          "vertex_type_map": { "person": 0, "software": 1 },
+          "edge_type_map": { "knows": 0, "created": 1 },
+          "vertex_prop": {
+            "person": [["id", "ID"], ["name", "String"], ["age", "Integer"]],
+            "software": [["id", "ID"], ["name", "String"], ["lang", "String"]]
+          },
+          "edge_prop": {
+            "knows": [["start_id", "ID"], ["end_id", "ID"], ["weight", "Double"]],
+            "created": [["start_id", "ID"], ["end_id", "ID"], ["weight", "Double"]]
+          }
+        }
+        "#;
+        LDBCGraphSchema::from_json(schema_json.to_string()).expect("Parse schema error!")
+    }
+
+    #[test]
+    fn resolve_props_test() {
+        let schema = modern_schema();
+        let mut params = QueryParams::<Vertex>::default();
+        params.props = Some(vec!["name".into(), "age".into()]);
+        params.resolve_props(&schema).unwrap();
+        assert_eq!(params.props, Some(vec![PropKey::Id(1), PropKey::Id(2)]));
+
+        let mut params = QueryParams::<Vertex>::default();
+        params.labels = vec![Label::Str("software".to_string())];
+        params.props = Some(vec!["lang".into()]);
+        params.resolve_props(&schema).unwrap();
+        assert_eq!(params.labels, vec![Label::Id(1)]);
+        assert_eq!(params.props, Some(vec![PropKey::Id(2)]));
+
+        // "weight" is a property of edges only
+        let mut params = QueryParams::<Vertex>::default();
+        params.props = Some(vec!["weight".into()]);
+        assert!(params.resolve_props(&schema).is_err());
+        let mut params = QueryParams::<Edge>::default();
+        params.labels = vec![Label::Str("knows".to_string())];
+        params.props = Some(vec!["weight".into()]);
+        assert!(params.resolve_props(&schema).is_ok());
+        // all edge labels in the schema are searched if no label is given
+        let mut params = QueryParams::<Edge>::default();
+        params.props = Some(vec!["weight".into()]);
+        params.resolve_props(&schema).unwrap();
+        assert_eq!(params.props, Some(vec![PropKey::Id(2)]));
+    }
+}
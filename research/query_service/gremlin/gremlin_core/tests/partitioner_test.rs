@@ -0,0 +1,47 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+#[cfg(test)]
+mod test {
+    use gremlin_core::{HashPartitioner, Partitioner, ID};
+
+    #[test]
+    fn hash_partitioner_test() {
+        let partitioner = HashPartitioner { num_servers: 2 };
+        let workers = 4;
+        let mut counts = vec![0; 8];
+        for id in 0..8000 as ID {
+            let partition = partitioner.get_partition(&id, workers).unwrap();
+            assert!(partition < 8);
+            // stable for the same id
+            assert_eq!(partition, partitioner.get_partition(&id, workers).unwrap());
+            counts[partition as usize] += 1;
+        }
+        // roughly even: each partition should hold about 1000 ids
+        for count in counts {
+            assert!(count > 800 && count < 1200, "uneven partition of {} ids", count);
+        }
+    }
+
+    #[test]
+    fn hash_partitioner_high_bits_test() {
+        // ids that only differ in the high 64 bits are still distributed
+        let partitioner = HashPartitioner { num_servers: 1 };
+        let partitions = (0..64 as ID)
+            .map(|label| partitioner.get_partition(&(label << 64), 4).unwrap())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(partitions.len(), 4);
+    }
+}
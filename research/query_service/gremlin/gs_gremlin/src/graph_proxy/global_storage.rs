@@ -17,7 +17,8 @@ use dyn_type::{Object, Primitives};
 use gremlin_core::graph_proxy::from_fn;
 use gremlin_core::structure::LabelId as RuntimeLabelId;
 use gremlin_core::structure::{
-    DefaultDetails, Direction, DynDetails, Edge, Label, PropKey, QueryParams, Statement, Vertex,
+    CachedGraph, DefaultDetails, Direction, DynDetails, Edge, Label, PropKey, QueryParams,
+    Statement, Vertex,
 };
 use gremlin_core::{filter_limit, limit_n, str_to_dyn_error, IterList};
 use gremlin_core::{register_graph, DynResult, GraphProxy, ID};
@@ -60,7 +61,7 @@ pub fn create_gs_store<V, VI, E, EI>(
         store,
         partition_manager,
     };
-    register_graph(Arc::new(graph));
+    register_graph(Arc::new(CachedGraph::new(graph)));
 }
 
 impl<V, VI, E, EI> GraphProxy for GraphScopeStore<V, VI, E, EI>